@@ -1,15 +1,20 @@
-use std::{fs, time::Instant};
+use std::{fs, path::PathBuf, time::Instant};
 
-use jamalang::parser::parse_ast;
+use jamalang::parser::{imports::ImportResolver, parse_ast_with};
 
-pub fn cmd(path: String, time_parsing: bool) -> anyhow::Result<()> {
+use crate::diagnostics::report;
+
+pub fn cmd(path: String, time_parsing: bool, lib_dirs: Vec<PathBuf>) -> anyhow::Result<()> {
     let script = fs::read_to_string(path.clone())?;
+    let mut resolver = ImportResolver::new(lib_dirs);
 
     let file = if !time_parsing {
-        parse_ast(&path, &script)?
+        parse_ast_with(&path, &script, &mut resolver)
+            .map_err(|diagnostics| report(&path, diagnostics))?
     } else {
         let start = Instant::now();
-        let file = parse_ast(&path, &script)?;
+        let file = parse_ast_with(&path, &script, &mut resolver)
+            .map_err(|diagnostics| report(&path, diagnostics))?;
         println!("Time: {:?}", start.elapsed());
         file
     };
@@ -1,32 +1,37 @@
 use std::{fs, path::PathBuf, time::Instant};
 
-use jamalang::parser::parse_ast;
-use jamalang::compiler::Compiler;
 use inkwell::context::Context;
+use jamalang::compiler::Compiler;
+use jamalang::parser::{imports::ImportResolver, parse_ast_with};
 
-use crate::CompileTarget;
+use crate::{diagnostics::report, CompileTarget};
 
 pub fn cmd(
     path: String,
     target: CompileTarget,
     destination: Option<String>,
     time_compilation: bool,
+    no_bounds_checks: bool,
+    lib_dirs: Vec<PathBuf>,
 ) -> anyhow::Result<()> {
     let script = fs::read_to_string(path.clone())?;
 
-    let file = parse_ast(&path, &script)?;
+    let file = parse_ast_with(&path, &script, &mut ImportResolver::new(lib_dirs))
+        .map_err(|diagnostics| report(&path, diagnostics))?;
 
     let context = Context::create();
     let module = context.create_module("main");
     let builder = context.create_builder();
 
     let mut compiler = Compiler::new(&context, &module, &builder);
+    compiler.bounds_checks = !no_bounds_checks;
+
+    let start = Instant::now();
+    compiler
+        .compile(file)
+        .map_err(|diagnostics| report(&path, diagnostics))?;
 
-    if !time_compilation {
-        compiler.compile(file);
-    } else {
-        let start = Instant::now();
-        compiler.compile(file);
+    if time_compilation {
         println!("Time: {:?}", start.elapsed());
     }
 
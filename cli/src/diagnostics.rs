@@ -0,0 +1,26 @@
+use std::{collections::HashMap, fs};
+
+use jamalang::diagnostic::Diagnostic;
+
+/// Prints each diagnostic to stderr, quoting the source of the file it points into.
+pub fn report(path: &str, diagnostics: Vec<Diagnostic>) -> anyhow::Error {
+    let mut sources = HashMap::new();
+
+    for diagnostic in &diagnostics {
+        let file = diagnostic
+            .primary_span()
+            .map_or_else(|| path.to_owned(), |span| span.file.to_string());
+        let source = sources
+            .entry(file.clone())
+            .or_insert_with(|| fs::read_to_string(&file).unwrap_or_default());
+
+        eprintln!("{}", diagnostic.render(source));
+    }
+
+    anyhow::anyhow!(
+        "could not compile `{}` due to {} previous error{}",
+        path,
+        diagnostics.len(),
+        if diagnostics.len() == 1 { "" } else { "s" }
+    )
+}
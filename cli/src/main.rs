@@ -1,7 +1,10 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand, ValueEnum};
 
 mod ast;
 mod compile;
+mod diagnostics;
 mod run;
 
 #[derive(Parser)]
@@ -18,6 +21,9 @@ enum Commands {
         file: String,
         #[arg(default_value = "false", long, short)]
         time_parsing: bool,
+        /// Additional directories to search for imports. Can be repeated.
+        #[arg(long = "lib-dir")]
+        lib_dirs: Vec<PathBuf>,
     },
     /// Runs a Jamalang file, in JIT mode.
     Run {
@@ -26,6 +32,12 @@ enum Commands {
         /// Whether to time the execution of the code, not including the compilation.
         #[arg(default_value = "false", long, short)]
         time_execution: bool,
+        /// Skip checking that array indices are in bounds at runtime.
+        #[arg(default_value = "false", long)]
+        no_bounds_checks: bool,
+        /// Additional directories to search for imports. Can be repeated.
+        #[arg(long = "lib-dir")]
+        lib_dirs: Vec<PathBuf>,
     },
     /// Compiles a Jamalang file to a binary, or to IR.
     Compile {
@@ -40,6 +52,12 @@ enum Commands {
         /// Whether to time the compilation.
         #[arg(default_value = "false", long)]
         time_compilation: bool,
+        /// Skip checking that array indices are in bounds at runtime.
+        #[arg(default_value = "false", long)]
+        no_bounds_checks: bool,
+        /// Additional directories to search for imports. Can be repeated.
+        #[arg(long = "lib-dir")]
+        lib_dirs: Vec<PathBuf>,
     },
 }
 
@@ -53,16 +71,31 @@ fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
     match args.command {
-        Commands::Ast { file, time_parsing } => ast::cmd(file, time_parsing),
+        Commands::Ast {
+            file,
+            time_parsing,
+            lib_dirs,
+        } => ast::cmd(file, time_parsing, lib_dirs),
         Commands::Run {
             file,
             time_execution,
-        } => run::cmd(file, time_execution),
+            no_bounds_checks,
+            lib_dirs,
+        } => run::cmd(file, time_execution, no_bounds_checks, lib_dirs),
         Commands::Compile {
             file,
             target,
             destination,
             time_compilation,
-        } => compile::cmd(file, target, destination, time_compilation),
+            no_bounds_checks,
+            lib_dirs,
+        } => compile::cmd(
+            file,
+            target,
+            destination,
+            time_compilation,
+            no_bounds_checks,
+            lib_dirs,
+        ),
     }
 }
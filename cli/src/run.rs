@@ -1,21 +1,32 @@
-use std::{fs, time::Instant};
+use std::{fs, path::PathBuf, time::Instant};
 
-use jamalang::parser::parse_ast;
-use jamalang::compiler::Compiler;
 use inkwell::{context::Context, execution_engine::JitFunction, OptimizationLevel};
+use jamalang::compiler::Compiler;
+use jamalang::parser::{imports::ImportResolver, parse_ast_with};
+
+use crate::diagnostics::report;
 
-pub fn cmd(path: String, time_execution: bool) -> anyhow::Result<()> {
+pub fn cmd(
+    path: String,
+    time_execution: bool,
+    no_bounds_checks: bool,
+    lib_dirs: Vec<PathBuf>,
+) -> anyhow::Result<()> {
     let script = fs::read_to_string(path.clone())?;
 
-    let file = parse_ast(&path, &script)?;
+    let file = parse_ast_with(&path, &script, &mut ImportResolver::new(lib_dirs))
+        .map_err(|diagnostics| report(&path, diagnostics))?;
 
     let context = Context::create();
     let module = context.create_module("main");
     let builder = context.create_builder();
 
     let mut compiler = Compiler::new(&context, &module, &builder);
+    compiler.bounds_checks = !no_bounds_checks;
 
-    compiler.compile(file);
+    compiler
+        .compile(file)
+        .map_err(|diagnostics| report(&path, diagnostics))?;
 
     let execution_engine = compiler
         .module
@@ -0,0 +1,678 @@
+use crate::{
+    diagnostic::{Diagnostic, ErrorCode, Span},
+    parser::{
+        ast::Type,
+        expr::{Expr, Op},
+    },
+};
+
+use super::{closest, is_float, is_integer, key, known, Checker, Signature};
+
+impl Checker<'_> {
+    /// Infers the type of an expression, reporting any errors inside it. Returns `None` if the type
+    /// can't be worked out because of an earlier error.
+    ///
+    /// The expected type is the type the expression is used as, if that is known. Expressions made
+    /// only of literals take it if they can, so that the `1` in `x: Long = 1` is a `Long`, and
+    /// anything else is checked against it by the caller.
+    pub(super) fn check_expr(&mut self, expr: &Expr, expected: Option<&Type>) -> Option<Type> {
+        let expected = match expected {
+            Some(kind) if is_literal(expr) && !literal_can_be(expr, kind) => None,
+            expected => expected,
+        };
+
+        match expr {
+            Expr::Float(value, _, span) => Some(self.check_float(*value, span, expected)),
+            Expr::Integer(_, Some(suffix), _) => Some(suffix.clone()),
+            Expr::Integer(value, None, span) => {
+                Some(self.check_integer(*value, span, expected, span))
+            }
+            Expr::Bool(..) => Some(Type::Bool),
+            Expr::Char(..) => Some(Type::Char),
+            Expr::Str(..) => Some(Type::String),
+            Expr::Interpolation(parts, _) => {
+                for part in parts {
+                    let found = self.check_expr(part, None);
+                    self.expect_value(found, part.span(), "`void` can't be converted to a string");
+                }
+
+                Some(Type::String)
+            }
+            Expr::TypeNone(_) => Some(Type::Void),
+            Expr::Identifier(identifier, span) => self.lookup(identifier, span),
+            Expr::FunctionCall {
+                identifier,
+                parameters,
+                span,
+                ..
+            } => self.check_call(identifier, parameters, span),
+            Expr::UnaryMinus(operand, span) => match &**operand {
+                // A minus in front of a literal makes a negative literal, so `-2147483648` fits in a
+                // `SignedInt` and `-1` doesn't fit in an `UnsignedInt`.
+                Expr::Integer(value, None, literal) => {
+                    let kind = self.check_integer(value.wrapping_neg(), literal, expected, span);
+
+                    // Any negative `UnsignedInt` was just reported as not fitting in it.
+                    if kind == Type::UnsignedInt {
+                        return Some(kind);
+                    }
+
+                    self.negate(kind, span)
+                }
+                operand => {
+                    // Literals being negated, such as the ones in `-(1 + 2)`, are signed unless
+                    // something else decides their type.
+                    let signed = literal_type(expr);
+                    let expected = match expected {
+                        None if is_literal(operand) => Some(&signed),
+                        expected => expected,
+                    };
+                    let kind = self.check_expr(operand, expected)?;
+                    self.negate(kind, span)
+                }
+            },
+            Expr::Not(operand, span) => {
+                let kind = self.check_expr(operand, Some(&Type::Bool))?;
+                self.not(kind, span)
+            }
+            Expr::BitNot(operand, span) => {
+                let kind = self.check_expr(operand, expected)?;
+                self.bit_not(kind, span)
+            }
+            Expr::Array(elements, span) => self.check_array(elements, expected, span),
+            Expr::Object(fields, _) => {
+                // Fields are checked in the order they were written, but stored sorted by name.
+                let mut types = Vec::new();
+
+                for (name, value) in fields {
+                    let field = match expected {
+                        Some(Type::Record(fields)) => fields
+                            .iter()
+                            .find(|(field, _)| field == name)
+                            .map(|(_, kind)| kind),
+                        _ => None,
+                    };
+                    let found = self.check_expr(value, field);
+                    let found =
+                        self.expect_value(found, value.span(), "records can't contain `void`");
+                    types.push(found.map(|found| (name.clone(), found)));
+                }
+
+                let mut types = types.into_iter().collect::<Option<Vec<_>>>()?;
+                types.sort_by(|a, b| a.0.cmp(&b.0));
+
+                Some(Type::Record(types))
+            }
+            Expr::ArrayIndex { array, index, span } => {
+                let array = self.check_expr(array, None);
+                self.index(array, index, span)
+            }
+            Expr::Cast {
+                value,
+                r#type,
+                span,
+            } => {
+                let value = self.check_expr(value, None);
+                self.check_known(r#type, span);
+                let target = known(r#type)?;
+                Some(self.cast(value, target, span))
+            }
+            Expr::BinOp {
+                lhs,
+                op: Op::Range,
+                rhs,
+                span,
+            } => self.check_range(lhs, rhs, expected, span),
+            Expr::BinOp { lhs, op, rhs, span } => {
+                // Comparisons and logical operators give a `bool` whatever their operands are.
+                let expected = match op {
+                    Op::Lt | Op::Gt | Op::Lte | Op::Gte | Op::Eq | Op::NotEq => None,
+                    Op::And | Op::Or => Some(&Type::Bool),
+                    _ => expected,
+                };
+                let (lhs, rhs) = self.check_operands(lhs, rhs, expected);
+                self.binary_op(*op, lhs, rhs, span)
+            }
+        }
+    }
+
+    /// Works out the type of an integer literal without a suffix, given its value with any minus in
+    /// front of it. A literal expected to be a number takes that type, reporting it if it doesn't
+    /// fit. Any other literal is an `UnsignedInt` if it isn't negative, and otherwise a `SignedInt`,
+    /// or a `Long` if it is too big for either.
+    fn check_integer(
+        &mut self,
+        value: i64,
+        literal: &Span,
+        expected: Option<&Type>,
+        span: &Span,
+    ) -> Type {
+        let kind = match expected {
+            Some(kind) if is_integer(kind) || is_float(kind) => kind.clone(),
+            _ if u32::try_from(value).is_ok() => Type::UnsignedInt,
+            _ if i32::try_from(value).is_ok() => Type::SignedInt,
+            _ => Type::Long,
+        };
+        let fits = match kind {
+            Type::SignedInt => i32::try_from(value).is_ok(),
+            Type::UnsignedInt => u32::try_from(value).is_ok(),
+            _ => true,
+        };
+
+        if !fits {
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::LiteralOverflow,
+                    format!("`{}` doesn't fit in `{}`", value, kind),
+                )
+                .with_label(span.clone(), "this value is out of range"),
+            );
+        }
+
+        self.annotations.literals.insert(key(literal), kind.clone());
+        kind
+    }
+
+    /// Works out the type of a float literal, which is a `Double` unless it is expected to be a
+    /// `Float`, as literals only lose precision when they are used as one. A literal too big for a
+    /// `Float` is reported rather than becoming infinite.
+    fn check_float(&mut self, value: f64, literal: &Span, expected: Option<&Type>) -> Type {
+        let kind = match expected {
+            Some(Type::Float) => Type::Float,
+            _ => Type::Double,
+        };
+
+        if kind == Type::Float && value.is_finite() && (value as f32).is_infinite() {
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::LiteralOverflow,
+                    format!("`{:e}` doesn't fit in `Float`", value),
+                )
+                .with_label(literal.clone(), "this value is out of range"),
+            );
+        }
+
+        self.annotations.literals.insert(key(literal), kind.clone());
+        kind
+    }
+
+    /// Checks the two sides of a binary operator. A side made only of literals takes the type of
+    /// the other side, and if both are, they take the expected type or whichever of their own types
+    /// can hold both. A `Float` used with a `Double` is widened to one.
+    pub(super) fn check_operands(
+        &mut self,
+        lhs: &Expr,
+        rhs: &Expr,
+        expected: Option<&Type>,
+    ) -> (Option<Type>, Option<Type>) {
+        let (lhs_type, rhs_type) = match (is_literal(lhs), is_literal(rhs)) {
+            (true, false) => {
+                let rhs_type = self.check_expr(rhs, expected);
+                let lhs_type = self.check_expr(lhs, rhs_type.as_ref().or(expected));
+                (lhs_type, rhs_type)
+            }
+            (false, true) => {
+                let lhs_type = self.check_expr(lhs, expected);
+                let rhs_type = self.check_expr(rhs, lhs_type.as_ref().or(expected));
+                (lhs_type, rhs_type)
+            }
+            (true, true) => {
+                let kind = match expected {
+                    Some(kind) if literal_can_be(lhs, kind) && literal_can_be(rhs, kind) => {
+                        kind.clone()
+                    }
+                    _ => wider(literal_type(lhs), literal_type(rhs)),
+                };
+                (
+                    self.check_expr(lhs, Some(&kind)),
+                    self.check_expr(rhs, Some(&kind)),
+                )
+            }
+            (false, false) => (
+                self.check_expr(lhs, expected),
+                self.check_expr(rhs, expected),
+            ),
+        };
+
+        let lhs_type = self.widen(lhs_type, lhs, rhs_type.as_ref());
+        let rhs_type = self.widen(rhs_type, rhs, lhs_type.as_ref());
+        (lhs_type, rhs_type)
+    }
+
+    /// Widens a `Float` used with a `Double` to a `Double`, which never loses precision, and
+    /// returns its new type.
+    pub(super) fn widen(
+        &mut self,
+        found: Option<Type>,
+        expr: &Expr,
+        other: Option<&Type>,
+    ) -> Option<Type> {
+        match (found, other) {
+            (Some(Type::Float), Some(Type::Double)) => {
+                let span = expr.span();
+                self.annotations
+                    .widened
+                    .insert((span.file.clone(), span.start, span.end));
+                Some(Type::Double)
+            }
+            (found, _) => found,
+        }
+    }
+
+    /// Works out the type of a range such as `0..5`, which is an array of the numbers from the
+    /// start up to but not including the end. Both ends need to be literals.
+    fn check_range(
+        &mut self,
+        start: &Expr,
+        end: &Expr,
+        expected: Option<&Type>,
+        span: &Span,
+    ) -> Option<Type> {
+        let element = match expected {
+            Some(Type::Array { kind, .. }) => Some(&**kind),
+            _ => None,
+        };
+        let (start_type, end_type) = self.check_operands(start, end, element);
+        let kind = self.binary_op(Op::Range, start_type, end_type, span)?;
+
+        let len = match (literal_value(start), literal_value(end)) {
+            (Some(start), Some(end)) if is_float(&kind) => {
+                (end as u32).saturating_sub(start as u32)
+            }
+            (Some(start), Some(end)) => (end - start).max(0.0) as u32,
+            _ => {
+                self.report(
+                    Diagnostic::new(ErrorCode::Unsupported, "ranges must have constant bounds")
+                        .with_label(span.clone(), "this range isn't known at compile time"),
+                );
+                return None;
+            }
+        };
+
+        Some(Type::Array {
+            len,
+            kind: Box::new(kind),
+        })
+    }
+
+    fn check_call(&mut self, identifier: &str, parameters: &[Expr], span: &Span) -> Option<Type> {
+        let function = self.function(identifier);
+        let mut args = Vec::new();
+
+        for (index, parameter) in parameters.iter().enumerate() {
+            // Literals take the type of the parameter they are passed as.
+            let expected = function
+                .as_ref()
+                .and_then(|function| function.parameters.get(index))
+                .and_then(known);
+            let found = self.check_expr(parameter, expected.as_ref());
+            let found = self.expect_value(
+                found,
+                parameter.span(),
+                "`void` can't be passed to a function",
+            );
+            args.push((found, parameter.span()));
+        }
+
+        let signature = match function {
+            Some(signature) => signature,
+            // Builtins are overloaded by the types of their arguments, such as `println_n` for
+            // `println` with a `Float`.
+            None if BUILTINS.contains(&identifier) => {
+                self.check_argument_count(identifier, 1, args.len(), span)?;
+                let (arg, arg_span) = &args[0];
+                let arg = arg.as_ref()?;
+                let overload = signature(arg)
+                    .and_then(|suffix| self.function(&format!("{}_{}", identifier, suffix)));
+
+                if overload.is_none() {
+                    self.report(
+                        Diagnostic::new(
+                            ErrorCode::TypeMismatch,
+                            format!("`{}` can't take a `{}`", identifier, arg),
+                        )
+                        .with_label((*arg_span).clone(), "this value can't be passed to it"),
+                    );
+                }
+
+                overload?
+            }
+            None => {
+                self.report_undefined_function(identifier, span);
+                return None;
+            }
+        };
+
+        self.check_argument_count(identifier, signature.parameters.len(), args.len(), span);
+
+        for ((arg, arg_span), parameter) in args.into_iter().zip(&signature.parameters) {
+            let Some(parameter) = known(parameter) else {
+                continue;
+            };
+            let label = format!("`{}` takes a `{}` here", identifier, parameter);
+            self.expect(arg, &parameter, arg_span, label);
+        }
+
+        // A function called before its definition has its body checked early to find its type.
+        let return_type = match signature.return_type {
+            Some(return_type) => Some(return_type),
+            None => self.infer_return_type(identifier),
+        };
+        let Some(return_type) = return_type else {
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::UnknownReturnType,
+                    format!("cannot infer the return type of `{}` here", identifier),
+                )
+                .with_label(span.clone(), "called before any of its `return`s")
+                .with_note(format!(
+                    "give `{}` a return type, such as `function {}(): SignedInt`",
+                    identifier, identifier
+                )),
+            );
+            return None;
+        };
+
+        known(&return_type)
+    }
+
+    /// Reports a call with the wrong number of arguments, returning `None` if it has them.
+    fn check_argument_count(
+        &mut self,
+        identifier: &str,
+        expected: usize,
+        found: usize,
+        span: &Span,
+    ) -> Option<()> {
+        if expected == found {
+            return Some(());
+        }
+
+        self.report(
+            Diagnostic::new(
+                ErrorCode::ArgumentCount,
+                format!(
+                    "`{}` takes {}, but {} {} given",
+                    identifier,
+                    arguments(expected),
+                    found,
+                    if found == 1 { "was" } else { "were" }
+                ),
+            )
+            .with_label(span.clone(), format!("expected {}", arguments(expected))),
+        );
+        None
+    }
+
+    /// Reports a call to a function that doesn't exist, suggesting one with a similar name.
+    fn report_undefined_function(&mut self, identifier: &str, span: &Span) {
+        let diagnostic = Diagnostic::new(
+            ErrorCode::UndefinedFunction,
+            format!("cannot find function `{}`", identifier),
+        )
+        .with_label(span.clone(), "not found");
+        let names = self.functions.keys().map(String::as_str);
+
+        let diagnostic = match closest(identifier, names.chain(BUILTINS.iter().copied())) {
+            Some(name) => diagnostic.with_note(format!("did you mean `{}`?", name)),
+            None => diagnostic,
+        };
+
+        self.report(diagnostic);
+    }
+
+    /// Looks up a function, preferring those defined by the file being checked to builtins.
+    fn function(&self, identifier: &str) -> Option<Signature> {
+        if let Some(signature) = self.functions.get(identifier) {
+            return Some(signature.clone());
+        }
+
+        let parameter = match identifier {
+            "println_n" => Type::Float,
+            "println_d" => Type::Double,
+            "println_u" => Type::UnsignedInt,
+            "println_i" => Type::SignedInt,
+            "println_l" => Type::Long,
+            "println_c" => Type::Char,
+            "println_b" => Type::Bool,
+            "println_s" => Type::String,
+            _ => return None,
+        };
+
+        Some(Signature {
+            parameters: vec![parameter],
+            return_type: Some(Type::Void),
+        })
+    }
+
+    fn check_array(
+        &mut self,
+        elements: &[Expr],
+        expected: Option<&Type>,
+        span: &Span,
+    ) -> Option<Type> {
+        let expected = match expected {
+            Some(Type::Array { kind, .. }) => Some(&**kind),
+            _ => None,
+        };
+        let mut types = vec![None; elements.len()];
+
+        // Elements made only of literals take the type of the other elements, so the others are
+        // checked first.
+        for (index, element) in elements.iter().enumerate() {
+            if !is_literal(element) {
+                let found = self.check_expr(element, expected);
+                types[index] =
+                    self.expect_value(found, element.span(), "arrays can't contain `void`");
+            }
+        }
+
+        let literals = elements.iter().filter(|element| is_literal(element));
+        let kind = expected
+            .cloned()
+            .or_else(|| {
+                elements
+                    .iter()
+                    .zip(&types)
+                    .find(|(element, _)| !is_literal(element))
+                    .and_then(|(_, kind)| kind.clone())
+            })
+            .or_else(|| literals.map(literal_type).reduce(wider));
+
+        for (index, element) in elements.iter().enumerate() {
+            if is_literal(element) {
+                types[index] = self.check_expr(element, kind.as_ref());
+            }
+        }
+
+        let types = types.into_iter().collect::<Option<Vec<_>>>()?;
+        let Some(first) = types.first() else {
+            self.report(
+                Diagnostic::new(ErrorCode::Unsupported, "empty arrays aren't supported yet")
+                    .with_label(span.clone(), "this array has no elements"),
+            );
+            return None;
+        };
+
+        if let Some((found, element)) = types
+            .iter()
+            .zip(elements)
+            .find(|(found, _)| *found != first)
+        {
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::TypeMismatch,
+                    "array elements must all have the same type",
+                )
+                .with_label(
+                    element.span().clone(),
+                    format!(
+                        "this is a `{}`, but the first element is a `{}`",
+                        found, first
+                    ),
+                ),
+            );
+        }
+
+        Some(Type::Array {
+            len: types.len() as u32,
+            kind: Box::new(first.clone()),
+        })
+    }
+
+    /// Works out the type of an element of an array, given the type of the array.
+    pub(super) fn index(&mut self, array: Option<Type>, index: &Expr, span: &Span) -> Option<Type> {
+        let found = self.check_expr(index, None);
+
+        if let Some(found) = found.filter(|found| !is_integer(found)) {
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::TypeMismatch,
+                    format!("cannot index with `{}`", found),
+                )
+                .with_label(index.span().clone(), "array indices must be integers"),
+            );
+        }
+
+        match array? {
+            Type::Array { kind, .. } => Some(*kind),
+            array => {
+                self.report(
+                    Diagnostic::new(
+                        ErrorCode::TypeMismatch,
+                        format!("cannot index into `{}`", array),
+                    )
+                    .with_label(span.clone(), "only arrays can be indexed"),
+                );
+                None
+            }
+        }
+    }
+
+    /// Reports a `void` used where a value is needed, such as `println(None)`, and returns `None`
+    /// for it so that it isn't reported again.
+    pub(super) fn expect_value(
+        &mut self,
+        found: Option<Type>,
+        span: &Span,
+        label: &str,
+    ) -> Option<Type> {
+        match found {
+            Some(Type::Void) => {
+                self.report(
+                    Diagnostic::new(ErrorCode::TypeMismatch, "expected a value, found `void`")
+                        .with_label(span.clone(), label),
+                );
+                None
+            }
+            found => found,
+        }
+    }
+}
+
+/// The functions every program can call. Each is overloaded for the types of its arguments.
+const BUILTINS: &[&str] = &["println"];
+
+/// Writes out a number of arguments, such as `1 argument` or `2 arguments`.
+fn arguments(count: usize) -> String {
+    match count {
+        1 => "1 argument".to_owned(),
+        count => format!("{} arguments", count),
+    }
+}
+
+/// Returns the suffix used to find the overload of a function taking a value of the given type,
+/// such as `n` for `println_n`, or `None` if no overload can take it.
+fn signature(kind: &Type) -> Option<String> {
+    Some(match kind {
+        Type::Float => "n".to_owned(),
+        Type::Double => "d".to_owned(),
+        Type::UnsignedInt => "u".to_owned(),
+        Type::SignedInt => "i".to_owned(),
+        Type::Long => "l".to_owned(),
+        Type::Char => "c".to_owned(),
+        Type::Bool => "b".to_owned(),
+        Type::String => "s".to_owned(),
+        Type::Array { len, kind } => format!("a{}{}", len, signature(kind)?),
+        Type::Void | Type::Record(_) | Type::Custom(_) => return None,
+    })
+}
+
+/// Returns whether an expression is made only of numeric literals without a suffix, `-`, `~` and
+/// arithmetic or bitwise operators, such as `2 * -3`. Such an expression has no type of its own,
+/// and takes the type it is used as.
+pub(super) fn is_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::Integer(_, None, _) | Expr::Float(..) => true,
+        Expr::UnaryMinus(operand, _) | Expr::BitNot(operand, _) => is_literal(operand),
+        Expr::BinOp { lhs, op, rhs, .. } => {
+            !op.is_comparison()
+                && !matches!(op, Op::And | Op::Or | Op::Range)
+                && is_literal(lhs)
+                && is_literal(rhs)
+        }
+        _ => false,
+    }
+}
+
+/// Returns whether an expression made only of literals can have the given type. Integer literals
+/// can be any number, float literals any float, and `~` and the bitwise operators need integers.
+fn literal_can_be(expr: &Expr, kind: &Type) -> bool {
+    match expr {
+        Expr::Integer(..) => is_integer(kind) || is_float(kind),
+        Expr::Float(..) => is_float(kind),
+        Expr::UnaryMinus(operand, _) => literal_can_be(operand, kind),
+        Expr::BitNot(operand, _) => is_integer(kind) && literal_can_be(operand, kind),
+        Expr::BinOp { lhs, op, rhs, .. } => {
+            let bitwise = matches!(
+                op,
+                Op::BitAnd | Op::BitOr | Op::BitXor | Op::ShiftLeft | Op::ShiftRight
+            );
+            (!bitwise || is_integer(kind)) && literal_can_be(lhs, kind) && literal_can_be(rhs, kind)
+        }
+        _ => false,
+    }
+}
+
+/// Returns the type an expression made only of literals has when nothing decides it: a `Double` if
+/// it has any float literals, a `SignedInt` if it is negated, and otherwise an `UnsignedInt`, or a
+/// `Long` if a literal is too big for the integer type.
+fn literal_type(expr: &Expr) -> Type {
+    match expr {
+        Expr::Integer(value, ..) if u32::try_from(*value).is_ok() => Type::UnsignedInt,
+        Expr::Integer(..) => Type::Long,
+        Expr::UnaryMinus(operand, _) => match &**operand {
+            Expr::Integer(value, ..) if i32::try_from(value.wrapping_neg()).is_ok() => {
+                Type::SignedInt
+            }
+            Expr::Integer(..) => Type::Long,
+            operand => match literal_type(operand) {
+                Type::UnsignedInt => Type::SignedInt,
+                kind => kind,
+            },
+        },
+        Expr::BitNot(operand, _) => literal_type(operand),
+        Expr::BinOp { lhs, rhs, .. } => wider(literal_type(lhs), literal_type(rhs)),
+        _ => Type::Double,
+    }
+}
+
+/// Returns whichever of two literal types can hold the values of both.
+fn wider(a: Type, b: Type) -> Type {
+    match (a, b) {
+        (Type::Double, _) | (_, Type::Double) => Type::Double,
+        (Type::Long, _) | (_, Type::Long) => Type::Long,
+        (Type::SignedInt, _) | (_, Type::SignedInt) => Type::SignedInt,
+        (kind, _) => kind,
+    }
+}
+
+/// Returns the value of a numeric literal, or of one with a minus in front of it such as `-1`.
+fn literal_value(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Integer(value, _, _) => Some(*value as u64 as f64),
+        Expr::Float(value, _, _) => Some(*value),
+        Expr::UnaryMinus(operand, _) => literal_value(operand).map(|value| -value),
+        _ => None,
+    }
+}
@@ -0,0 +1,1215 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    diagnostic::{Diagnostic, ErrorCode, Span},
+    parser::{
+        ast::{AssignmentType, Statement, Type},
+        expr::Expr,
+        imports::namespace,
+        JamalangFile,
+    },
+};
+
+mod expr;
+mod op;
+
+/// The types of a function's parameters, and the type it returns.
+#[derive(Debug, Clone, PartialEq)]
+struct Signature {
+    parameters: Vec<Type>,
+    /// `None` until the function's body is checked if it has to be inferred from its `return`s.
+    return_type: Option<Type>,
+}
+
+/// What checking works out about a program that codegen needs, which is written onto it once it
+/// has been checked. Expressions and definitions are keyed by the file and offsets of their spans.
+#[derive(Debug, Default)]
+struct Annotations {
+    /// The type each numeric literal without a suffix is used as.
+    literals: HashMap<(Arc<str>, usize), Type>,
+    /// The `Float`s used with a `Double`, which are widened to one.
+    widened: HashSet<(Arc<str>, usize, usize)>,
+    /// The return types inferred for functions without a written one.
+    return_types: HashMap<(Arc<str>, usize), Type>,
+}
+
+/// The `return`s of a function being checked.
+struct Returns {
+    /// The name the function is called by, or `None` if it isn't the first definition of the name
+    /// and so is never called.
+    name: Option<String>,
+    /// The return type, if it was written.
+    hint: Option<Type>,
+    /// The type returned by the first `return` of a function without a written return type, and
+    /// where that `return` is.
+    inferred: Option<(Type, Span)>,
+}
+
+/// The names a file makes available to the files importing it, already prefixed with its
+/// namespace.
+#[derive(Debug, Clone, Default)]
+struct Exports {
+    functions: Vec<(String, Signature)>,
+    globals: Vec<(String, Option<Type>)>,
+}
+
+/// Checks a file and everything it imports before any code is generated for it, returning every
+/// error that was found.
+///
+/// The imports at the top level of each file are moved to its start first, as they run before the
+/// rest of it. Names are then resolved with the same scoping rules as codegen, and the type of every
+/// expression is inferred and checked against the places it is used, so codegen only sees
+/// well-typed programs. The types worked out along the way are then written onto the program: each
+/// numeric literal gets the type it is used as, each `Float` used with a `Double` is cast to one,
+/// and functions without a written return type get the one inferred for them.
+pub fn check(file: &mut JamalangFile) -> Result<(), Vec<Diagnostic>> {
+    hoist_imports(&mut file.statements);
+
+    let mut imports = HashMap::new();
+    let mut annotations = Annotations::default();
+    let mut diagnostics = Vec::new();
+
+    Checker::new(
+        &file.statements,
+        &mut imports,
+        &mut annotations,
+        &mut diagnostics,
+    )
+    .check_statements(&file.statements);
+
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+
+    annotate(&mut file.statements, &annotations);
+    Ok(())
+}
+
+struct Checker<'a> {
+    /// The functions that can be called by name, which are those defined by the file being checked
+    /// and those of the files it imports. Functions are global wherever they are defined, and are
+    /// all declared before anything is checked, so they can be called before their definitions.
+    functions: HashMap<String, Signature>,
+    /// The type of each variable in scope, innermost scope last. A type is `None` if it couldn't be
+    /// worked out because of an earlier error.
+    scopes: Vec<HashMap<String, Option<Type>>>,
+    /// Where each variable of each scope in `scopes` is first defined, including those that
+    /// haven't been reached yet, to tell a variable used too early from one that doesn't exist.
+    definitions: Vec<HashMap<String, Span>>,
+    /// The functions whose first definition has been checked, which is the one they are called by.
+    checked_functions: HashSet<String>,
+    /// The functions whose bodies are being checked, to tell a function calling itself before any
+    /// of its `return`s from one that just hasn't been checked yet.
+    checking: HashSet<String>,
+    /// The top-level statements of the file being checked, where the functions called before
+    /// their definitions are checked are found.
+    statements: &'a [Statement],
+    /// The `return`s of the functions being checked, innermost last.
+    returns: Vec<Returns>,
+    /// The exports of the files checked so far, keyed by their canonical path.
+    imports: &'a mut HashMap<String, Exports>,
+    annotations: &'a mut Annotations,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'a> {
+    /// Creates a checker for the top level of a file with the given statements.
+    fn new(
+        statements: &'a [Statement],
+        imports: &'a mut HashMap<String, Exports>,
+        annotations: &'a mut Annotations,
+        diagnostics: &'a mut Vec<Diagnostic>,
+    ) -> Self {
+        let mut functions = HashMap::new();
+        find_functions(statements, "", &mut functions);
+        find_redefinitions(statements, &mut HashMap::new(), diagnostics);
+
+        // Like codegen, the functions of the files imported at the top level are declared up front
+        // too, so that they can be called anywhere in the file.
+        for statement in statements {
+            if let Statement::ForeignModule {
+                path,
+                file: Some(file),
+                ..
+            } = statement
+            {
+                let prefix = format!("{}.", namespace(path));
+                find_functions(&file.statements, &prefix, &mut functions);
+            }
+        }
+
+        Self {
+            functions,
+            scopes: vec![HashMap::new()],
+            definitions: vec![find_variables(statements)],
+            checked_functions: HashSet::new(),
+            checking: HashSet::new(),
+            statements,
+            returns: Vec::new(),
+            imports,
+            annotations,
+            diagnostics,
+        }
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn check_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.check_statement(statement);
+        }
+    }
+
+    fn check_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Assignment {
+                identifier,
+                type_hint,
+                indices,
+                r#type,
+                value,
+                span,
+            } => self.check_assignment(
+                identifier,
+                type_hint.as_ref(),
+                indices,
+                *r#type,
+                value,
+                span,
+            ),
+            Statement::FunctionDefinition {
+                identifier,
+                parameters,
+                return_type_hint,
+                body,
+                span,
+                ..
+            } => self.check_function(
+                identifier,
+                parameters,
+                return_type_hint.as_ref(),
+                body,
+                span,
+            ),
+            Statement::Return { value, span } => self.check_return(value, span),
+            Statement::WhileLoop {
+                condition, body, ..
+            } => {
+                self.check_condition(condition);
+                self.check_statements(body);
+            }
+            Statement::ForLoop {
+                identifier,
+                identifier_type_hint,
+                expression,
+                body,
+                span,
+            } => {
+                let element = match self.check_expr(expression, None) {
+                    Some(Type::Array { kind, .. }) => Some(*kind),
+                    Some(found) => {
+                        self.report(
+                            Diagnostic::new(
+                                ErrorCode::TypeMismatch,
+                                format!("cannot loop over `{}`", found),
+                            )
+                            .with_label(
+                                expression.span().clone(),
+                                "only arrays can be looped over",
+                            ),
+                        );
+                        None
+                    }
+                    None => None,
+                };
+
+                if let Some(hint) = identifier_type_hint {
+                    self.check_known(hint, span);
+                }
+
+                if let (Some(hint), Some(element)) =
+                    (identifier_type_hint.as_ref().and_then(known), &element)
+                {
+                    if hint != *element {
+                        self.report(
+                            Diagnostic::new(
+                                ErrorCode::TypeMismatch,
+                                format!("expected `{}`, found `{}`", hint, element),
+                            )
+                            .with_label(
+                                expression.span().clone(),
+                                format!("the elements of this array are `{}`s", element),
+                            ),
+                        );
+                    }
+                }
+
+                self.declare(identifier, element);
+                self.check_statements(body);
+            }
+            Statement::IfStatement {
+                condition,
+                body,
+                else_ifs,
+                else_body,
+                ..
+            } => {
+                self.check_condition(condition);
+                self.check_statements(body);
+
+                for (condition, body) in else_ifs {
+                    self.check_condition(condition);
+                    self.check_statements(body);
+                }
+
+                self.check_statements(else_body);
+            }
+            Statement::ForeignModule { path, file, .. } => self.check_import(path, file.as_ref()),
+            Statement::Expr { expr, .. } => {
+                self.check_expr(expr, None);
+            }
+            Statement::Comment(..) => (),
+        }
+    }
+
+    fn check_function(
+        &mut self,
+        identifier: &str,
+        parameters: &[(String, Type)],
+        return_type_hint: Option<&Type>,
+        body: &[Statement],
+        span: &Span,
+    ) {
+        // Later definitions of a name are checked, but never called.
+        let name = self
+            .checked_functions
+            .insert(identifier.to_owned())
+            .then(|| identifier.to_owned());
+
+        for (parameter, kind) in parameters {
+            self.check_known(kind, span);
+
+            if contains_void(kind) {
+                self.report(
+                    Diagnostic::new(
+                        ErrorCode::VoidValue,
+                        format!("parameter `{}` can't have the type `{}`", parameter, kind),
+                    )
+                    .with_label(span.clone(), "parameters can't be `void`"),
+                );
+            }
+        }
+
+        if let Some(hint) = return_type_hint {
+            self.check_known(hint, span);
+        }
+
+        if let Some(hint) = return_type_hint.filter(|hint| **hint != Type::Void) {
+            if contains_void(hint) {
+                self.report(
+                    Diagnostic::new(
+                        ErrorCode::VoidValue,
+                        format!("`{}` can't return a `{}`", identifier, hint),
+                    )
+                    .with_label(span.clone(), "only a whole return type can be `void`"),
+                );
+            }
+        }
+
+        let returns = self.check_body(name.clone(), parameters, return_type_hint, body);
+
+        let return_type = match return_type_hint {
+            Some(hint) => known(hint),
+            None => {
+                // Functions that never return a value are `void`.
+                let return_type = returns.inferred.map_or(Type::Void, |(kind, _)| kind);
+                self.annotations
+                    .return_types
+                    .insert(key(span), return_type.clone());
+
+                if let Some(signature) = name.and_then(|name| self.functions.get_mut(&name)) {
+                    signature.return_type = Some(return_type.clone());
+                }
+
+                Some(return_type)
+            }
+        };
+
+        if let Some(return_type) = return_type.filter(|kind| *kind != Type::Void) {
+            if !always_returns(body) {
+                self.report(
+                    Diagnostic::new(
+                        ErrorCode::MissingReturn,
+                        format!("`{}` doesn't return a value on every path", identifier),
+                    )
+                    .with_label(
+                        span.clone(),
+                        format!("this function returns a `{}`", return_type),
+                    )
+                    .with_note("the end of the function can be reached without a `return`"),
+                );
+            }
+        }
+    }
+
+    /// Checks the body of a function in a scope of its own, returning what its `return`s return.
+    fn check_body(
+        &mut self,
+        name: Option<String>,
+        parameters: &[(String, Type)],
+        return_type_hint: Option<&Type>,
+        body: &[Statement],
+    ) -> Returns {
+        if let Some(name) = &name {
+            self.checking.insert(name.clone());
+        }
+
+        self.scopes.push(
+            parameters
+                .iter()
+                .map(|(name, kind)| (name.clone(), known(kind)))
+                .collect(),
+        );
+        self.definitions.push(find_variables(body));
+        self.returns.push(Returns {
+            name,
+            hint: return_type_hint.cloned(),
+            inferred: None,
+        });
+        self.check_statements(body);
+        let returns = self.returns.pop().expect("returns were pushed above");
+        self.definitions.pop();
+        self.scopes.pop();
+
+        if let Some(name) = &returns.name {
+            self.checking.remove(name);
+        }
+
+        returns
+    }
+
+    /// Works out the return type of a function without a written one that is called before its
+    /// definition is checked, by checking its body early with the top-level variables defined so
+    /// far in scope. Anything wrong with the body is reported once its definition is reached, so
+    /// nothing is reported or recorded here. Returns `None` if the function is calling itself
+    /// before any of its `return`s, as its type isn't known yet.
+    fn infer_return_type(&mut self, identifier: &str) -> Option<Type> {
+        if self.checking.contains(identifier) || self.checked_functions.contains(identifier) {
+            return None;
+        }
+
+        let Some(Statement::FunctionDefinition {
+            parameters, body, ..
+        }) = find_definition(self.statements, identifier)
+        else {
+            return None;
+        };
+        let globals = (self.scopes[0].clone(), self.definitions[0].clone());
+        let scopes = std::mem::replace(&mut self.scopes, vec![globals.0]);
+        let definitions = std::mem::replace(&mut self.definitions, vec![globals.1]);
+        let checked_functions = self.checked_functions.clone();
+        let annotations = std::mem::take(&mut *self.annotations);
+        let diagnostics = std::mem::take(&mut *self.diagnostics);
+
+        let returns = self.check_body(Some(identifier.to_owned()), parameters, None, body);
+
+        self.scopes = scopes;
+        self.definitions = definitions;
+        self.checked_functions = checked_functions;
+        *self.annotations = annotations;
+        *self.diagnostics = diagnostics;
+
+        returns.inferred.map(|(kind, _)| kind)
+    }
+
+    /// Checks that a `return` returns the function's return type. Functions without a written one
+    /// take the type of their first `return`, which the others must then match.
+    fn check_return(&mut self, value: &Expr, span: &Span) {
+        let expected = self.returns.last().and_then(|returns| match &returns.hint {
+            Some(hint) => known(hint),
+            None => returns.inferred.as_ref().map(|(kind, _)| kind.clone()),
+        });
+        let found = self.check_expr(value, expected.as_ref());
+
+        let Some(returns) = self.returns.last_mut() else {
+            self.report(
+                Diagnostic::new(ErrorCode::Unsupported, "`return` outside of a function")
+                    .with_label(span.clone(), "only functions can return"),
+            );
+            return;
+        };
+
+        match (&returns.hint, &returns.inferred) {
+            (Some(hint), _) => {
+                if let Some(hint) = known(hint) {
+                    let label = format!("expected `{}` because of the return type", hint);
+                    self.expect(found, &hint, span, label);
+                }
+            }
+            (None, Some((inferred, first))) => {
+                let label = format!(
+                    "expected `{}` because of the `return` on line {}",
+                    inferred, first.line
+                );
+                let inferred = inferred.clone();
+                self.expect(found, &inferred, span, label);
+            }
+            (None, None) => {
+                let Some(found) = found else {
+                    return;
+                };
+
+                returns.inferred = Some((found.clone(), span.clone()));
+
+                // Calls later on in the function, such as recursive ones, can now be checked.
+                if let Some(name) = returns.name.clone() {
+                    if let Some(signature) = self.functions.get_mut(&name) {
+                        signature.return_type = Some(found);
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_assignment(
+        &mut self,
+        identifier: &str,
+        type_hint: Option<&Type>,
+        indices: &[Expr],
+        r#type: AssignmentType,
+        value: &Expr,
+        span: &Span,
+    ) {
+        if let Some(hint) = type_hint {
+            self.check_known(hint, span);
+        }
+
+        if let Some(hint) = type_hint.filter(|hint| contains_void(hint)) {
+            self.check_expr(value, None);
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::VoidValue,
+                    format!("`{}` can't have the type `{}`", identifier, hint),
+                )
+                .with_label(span.clone(), "variables can't be `void`"),
+            );
+            self.declare(identifier, None);
+            return;
+        }
+
+        let hint = type_hint.and_then(known);
+
+        if r#type == AssignmentType::Set && indices.is_empty() && !identifier.contains('.') {
+            let mut found = self.check_expr(value, hint.as_ref());
+
+            if let Some(hint) = &hint {
+                let label = format!("expected `{}` because of the type hint", hint);
+                found = self.expect(found, hint, span, label);
+            }
+
+            if found == Some(Type::Void) {
+                self.report(
+                    Diagnostic::new(
+                        ErrorCode::VoidValue,
+                        format!("`{}` can't be `void`", identifier),
+                    )
+                    .with_label(value.span().clone(), "this has no value"),
+                );
+                found = None;
+            }
+
+            self.declare(identifier, found.or(hint));
+            return;
+        }
+
+        // Everything else assigns to something that already exists, which the value is used as.
+        let mut target = self.lookup(identifier, span);
+
+        for index in indices {
+            target = self.index(target, index, span);
+        }
+
+        let mut found = self.check_expr(value, hint.as_ref().or(target.as_ref()));
+
+        if let Some(hint) = &hint {
+            let label = format!("expected `{}` because of the type hint", hint);
+            found = self.expect(found, hint, span, label);
+        }
+
+        let Some(target) = target else {
+            return;
+        };
+        let label = format!("`{}` is a `{}`", identifier, target);
+
+        match r#type.operator() {
+            Some(op) => {
+                // A `Float` is widened to use it with a `Double`, which can't be stored back in it.
+                let found = self.widen(found, value, Some(&target));
+                let lhs = match (&target, &found) {
+                    (Type::Float, Some(Type::Double)) => Type::Double,
+                    _ => target.clone(),
+                };
+                let result = self.binary_op(op, Some(lhs), found, span);
+
+                if let Some(result) = result.filter(|result| *result != target) {
+                    self.report(
+                        Diagnostic::new(
+                            ErrorCode::TypeMismatch,
+                            format!("expected `{}`, found `{}`", target, result),
+                        )
+                        .with_label(span.clone(), label),
+                    );
+                }
+            }
+            None => {
+                self.expect(found, &target, span, label);
+            }
+        }
+    }
+
+    /// Checks the condition of an `if`, `elif` or `while`, which must be a `bool`.
+    fn check_condition(&mut self, condition: &Expr) {
+        let found = self.check_expr(condition, Some(&Type::Bool));
+
+        if let Some(found) = found.filter(|found| *found != Type::Bool) {
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::TypeMismatch,
+                    format!("expected `bool`, found `{}`", found),
+                )
+                .with_label(condition.span().clone(), "conditions must be `bool`s"),
+            );
+        }
+    }
+
+    /// Checks an imported file the first time it is imported, and brings the functions and
+    /// variables it defines into scope with its namespace in front of their names.
+    fn check_import(&mut self, path: &str, file: Option<&JamalangFile>) {
+        if let Some(file) = file {
+            let namespace = namespace(path);
+            let mut checker = Checker::new(
+                &file.statements,
+                &mut *self.imports,
+                &mut *self.annotations,
+                &mut *self.diagnostics,
+            );
+            checker.check_statements(&file.statements);
+
+            // Anything with a dot in its name came from a file the imported file imports.
+            let exports = Exports {
+                functions: checker
+                    .functions
+                    .into_iter()
+                    .filter(|(name, _)| !name.contains('.'))
+                    .map(|(name, signature)| (format!("{}.{}", namespace, name), signature))
+                    .collect(),
+                globals: checker
+                    .scopes
+                    .swap_remove(0)
+                    .into_iter()
+                    .filter(|(name, _)| !name.contains('.'))
+                    .map(|(name, kind)| (format!("{}.{}", namespace, name), kind))
+                    .collect(),
+            };
+            self.imports.insert(path.to_owned(), exports);
+        }
+
+        let Some(exports) = self.imports.get(path).cloned() else {
+            return;
+        };
+
+        self.functions.extend(exports.functions);
+
+        for (name, kind) in exports.globals {
+            self.declare(&name, kind);
+        }
+    }
+
+    fn declare(&mut self, identifier: &str, kind: Option<Type>) {
+        self.scopes
+            .last_mut()
+            .expect("scopes should hold at least one value")
+            .insert(identifier.to_owned(), kind);
+    }
+
+    /// Finds the type of a dotted identifier such as `p.x`, which is either a variable or a field of
+    /// a record stored in one. Returns `None` if its type isn't known, reporting the identifier if
+    /// it doesn't start with the name of a variable.
+    fn lookup(&mut self, identifier: &str, span: &Span) -> Option<Type> {
+        // Imported variables have dots in their names, so the longest matching prefix wins.
+        let Some((mut kind, fields)) = prefixes(identifier).find_map(|name| {
+            self.scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name))
+                .map(|kind| (kind.clone(), identifier[name.len()..].split('.').skip(1)))
+        }) else {
+            self.report_undefined_variable(identifier, span);
+            return None;
+        };
+
+        for field in fields {
+            let Some(record) = kind else {
+                break;
+            };
+
+            kind = match &record {
+                Type::Record(fields) => fields
+                    .iter()
+                    .find(|(name, _)| name == field)
+                    .map(|(_, kind)| kind.clone()),
+                _ => None,
+            };
+
+            if kind.is_none() {
+                self.report(
+                    Diagnostic::new(
+                        ErrorCode::UnknownField,
+                        format!("`{}` has no field `{}`", record, field),
+                    )
+                    .with_label(span.clone(), "unknown field"),
+                );
+            }
+        }
+
+        kind
+    }
+
+    /// Reports a variable that isn't in scope, either because it is defined later on or because it
+    /// doesn't exist, in which case a variable with a similar name is suggested.
+    fn report_undefined_variable(&mut self, identifier: &str, span: &Span) {
+        let definition = prefixes(identifier).find_map(|name| {
+            self.definitions
+                .iter()
+                .rev()
+                .find_map(|definitions| definitions.get(name))
+                .map(|definition| (name, definition.clone()))
+        });
+
+        let diagnostic = match definition {
+            Some((name, definition)) => Diagnostic::new(
+                ErrorCode::UndefinedVariable,
+                format!("`{}` is used before it is defined", name),
+            )
+            .with_label(span.clone(), "used here")
+            .with_secondary_label(definition, format!("`{}` is defined here", name)),
+            None => {
+                let diagnostic = Diagnostic::new(
+                    ErrorCode::UndefinedVariable,
+                    format!("cannot find variable `{}`", identifier),
+                )
+                .with_label(span.clone(), "not found in this scope");
+                let names = self.scopes.iter().flat_map(|scope| scope.keys());
+
+                match closest(identifier, names.map(String::as_str)) {
+                    Some(name) => diagnostic.with_note(format!("did you mean `{}`?", name)),
+                    None => diagnostic,
+                }
+            }
+        };
+
+        self.report(diagnostic);
+    }
+
+    /// Reports a type hint or cast that names a type which doesn't exist, such as `Foo[2]`,
+    /// suggesting a type with a similar name.
+    fn check_known(&mut self, kind: &Type, span: &Span) {
+        let Some(name) = unknown(kind) else {
+            return;
+        };
+        let diagnostic = Diagnostic::new(
+            ErrorCode::UnknownType,
+            format!("cannot find type `{}`", name),
+        )
+        .with_label(span.clone(), format!("`{}` isn't a type", name));
+
+        let diagnostic = match closest(name, TYPES.iter().copied()) {
+            Some(kind) => diagnostic.with_note(format!("did you mean `{}`?", kind)),
+            None => diagnostic,
+        };
+
+        self.report(diagnostic);
+    }
+
+    /// Checks that a value has the type it is used as, returning that type to carry on with.
+    fn expect(
+        &mut self,
+        found: Option<Type>,
+        expected: &Type,
+        span: &Span,
+        label: String,
+    ) -> Option<Type> {
+        let found = found?;
+
+        if found != *expected {
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::TypeMismatch,
+                    format!("expected `{}`, found `{}`", expected, found),
+                )
+                .with_label(span.clone(), label),
+            );
+        }
+
+        // Carry on as if the value had the right type.
+        Some(expected.clone())
+    }
+}
+
+/// Returns the names an identifier such as `math.origin.x` could refer to a variable by, longest
+/// first, since imported variables have dots in their names.
+fn prefixes(identifier: &str) -> impl Iterator<Item = &str> {
+    std::iter::once(identifier.len())
+        .chain(identifier.rmatch_indices('.').map(|(index, _)| index))
+        .map(|end| &identifier[..end])
+}
+
+/// Finds where each variable is first defined by some statements, including in the bodies of
+/// loops and `if`s, which share the scope they are in. Function bodies have their own scopes.
+fn find_variables(statements: &[Statement]) -> HashMap<String, Span> {
+    fn visit(statements: &[Statement], definitions: &mut HashMap<String, Span>) {
+        for statement in statements {
+            match statement {
+                Statement::Assignment {
+                    identifier,
+                    indices,
+                    r#type: AssignmentType::Set,
+                    span,
+                    ..
+                } if indices.is_empty() && !identifier.contains('.') => {
+                    definitions
+                        .entry(identifier.clone())
+                        .or_insert_with(|| span.clone());
+                }
+                Statement::ForLoop {
+                    identifier,
+                    body,
+                    span,
+                    ..
+                } => {
+                    definitions
+                        .entry(identifier.clone())
+                        .or_insert_with(|| span.clone());
+                    visit(body, definitions);
+                }
+                Statement::WhileLoop { body, .. } => visit(body, definitions),
+                Statement::IfStatement {
+                    body,
+                    else_ifs,
+                    else_body,
+                    ..
+                } => {
+                    visit(body, definitions);
+                    for (_, body) in else_ifs {
+                        visit(body, definitions);
+                    }
+                    visit(else_body, definitions);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let mut definitions = HashMap::new();
+    visit(statements, &mut definitions);
+    definitions
+}
+
+/// Declares each function defined by some statements, however deeply it is nested, with the
+/// given prefix in front of its name. A name defined twice is declared by its first definition,
+/// and `find_redefinitions` reports the others.
+fn find_functions(
+    statements: &[Statement],
+    prefix: &str,
+    functions: &mut HashMap<String, Signature>,
+) {
+    for statement in statements {
+        match statement {
+            Statement::FunctionDefinition {
+                identifier,
+                parameters,
+                return_type_hint,
+                body,
+                ..
+            } => {
+                // Functions that never `return` don't need their bodies checking to know they are
+                // `void`, so they can be called before their definitions.
+                let return_type = match return_type_hint {
+                    Some(hint) => Some(hint.clone()),
+                    None if !has_return(body) => Some(Type::Void),
+                    None => None,
+                };
+
+                functions
+                    .entry(format!("{}{}", prefix, identifier))
+                    .or_insert_with(|| Signature {
+                        parameters: parameters.iter().map(|(_, kind)| kind.clone()).collect(),
+                        return_type,
+                    });
+                find_functions(body, prefix, functions);
+            }
+            Statement::WhileLoop { body, .. } | Statement::ForLoop { body, .. } => {
+                find_functions(body, prefix, functions)
+            }
+            Statement::IfStatement {
+                body,
+                else_ifs,
+                else_body,
+                ..
+            } => {
+                find_functions(body, prefix, functions);
+                for (_, body) in else_ifs {
+                    find_functions(body, prefix, functions);
+                }
+                find_functions(else_body, prefix, functions);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Reports each function defined by some statements, however deeply it is nested, whose name was
+/// already defined, given where each name seen so far was first defined.
+fn find_redefinitions(
+    statements: &[Statement],
+    first: &mut HashMap<String, Span>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for statement in statements {
+        match statement {
+            Statement::FunctionDefinition {
+                identifier,
+                body,
+                span,
+                ..
+            } => {
+                match first.get(identifier) {
+                    Some(definition) => diagnostics.push(
+                        Diagnostic::new(
+                            ErrorCode::Redefinition,
+                            format!("the function `{}` is defined more than once", identifier),
+                        )
+                        .with_label(span.clone(), "defined again here")
+                        .with_secondary_label(
+                            definition.clone(),
+                            format!("`{}` is first defined here", identifier),
+                        )
+                        .with_note("functions are global, so every call would go to the first one"),
+                    ),
+                    None => {
+                        first.insert(identifier.clone(), span.clone());
+                    }
+                }
+
+                find_redefinitions(body, first, diagnostics);
+            }
+            Statement::WhileLoop { body, .. } | Statement::ForLoop { body, .. } => {
+                find_redefinitions(body, first, diagnostics)
+            }
+            Statement::IfStatement {
+                body,
+                else_ifs,
+                else_body,
+                ..
+            } => {
+                find_redefinitions(body, first, diagnostics);
+                for (_, body) in else_ifs {
+                    find_redefinitions(body, first, diagnostics);
+                }
+                find_redefinitions(else_body, first, diagnostics);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Finds the first definition of a function among some statements, however deeply it is nested,
+/// which is the one `find_functions` declares.
+fn find_definition<'s>(statements: &'s [Statement], identifier: &str) -> Option<&'s Statement> {
+    statements.iter().find_map(|statement| match statement {
+        Statement::FunctionDefinition {
+            identifier: name, ..
+        } if name == identifier => Some(statement),
+        Statement::FunctionDefinition { body, .. }
+        | Statement::WhileLoop { body, .. }
+        | Statement::ForLoop { body, .. } => find_definition(body, identifier),
+        Statement::IfStatement {
+            body,
+            else_ifs,
+            else_body,
+            ..
+        } => find_definition(body, identifier)
+            .or_else(|| {
+                else_ifs
+                    .iter()
+                    .find_map(|(_, body)| find_definition(body, identifier))
+            })
+            .or_else(|| find_definition(else_body, identifier)),
+        _ => None,
+    })
+}
+
+/// Moves the imports among some top-level statements to the start, keeping them in the order they
+/// were written, and does the same for the files they import. An imported file's functions can be
+/// called from anywhere in the importing file, so its top-level code has to run first.
+fn hoist_imports(statements: &mut [Statement]) {
+    statements.sort_by_key(|statement| !matches!(statement, Statement::ForeignModule { .. }));
+
+    for statement in statements {
+        if let Statement::ForeignModule {
+            file: Some(file), ..
+        } = statement
+        {
+            hoist_imports(&mut file.statements);
+        }
+    }
+}
+
+/// Returns the key of an expression or definition in `Annotations`.
+fn key(span: &Span) -> (Arc<str>, usize) {
+    (span.file.clone(), span.start)
+}
+
+/// Writes what checking worked out onto some statements and the files they import.
+fn annotate(statements: &mut [Statement], annotations: &Annotations) {
+    for statement in statements {
+        match statement {
+            Statement::Assignment { indices, value, .. } => {
+                for index in indices {
+                    annotate_expr(index, annotations);
+                }
+                annotate_expr(value, annotations);
+            }
+            Statement::FunctionDefinition {
+                return_type_hint,
+                body,
+                span,
+                ..
+            } => {
+                if return_type_hint.is_none() {
+                    *return_type_hint = annotations.return_types.get(&key(span)).cloned();
+                }
+                annotate(body, annotations);
+            }
+            Statement::Return { value, .. } | Statement::Expr { expr: value, .. } => {
+                annotate_expr(value, annotations)
+            }
+            Statement::WhileLoop {
+                condition: expr,
+                body,
+                ..
+            }
+            | Statement::ForLoop {
+                expression: expr,
+                body,
+                ..
+            } => {
+                annotate_expr(expr, annotations);
+                annotate(body, annotations);
+            }
+            Statement::IfStatement {
+                condition,
+                body,
+                else_ifs,
+                else_body,
+                ..
+            } => {
+                annotate_expr(condition, annotations);
+                annotate(body, annotations);
+                for (condition, body) in else_ifs {
+                    annotate_expr(condition, annotations);
+                    annotate(body, annotations);
+                }
+                annotate(else_body, annotations);
+            }
+            Statement::ForeignModule {
+                file: Some(file), ..
+            } => annotate(&mut file.statements, annotations),
+            _ => (),
+        }
+    }
+}
+
+/// Writes the types of the literals in an expression onto them, and casts the `Float`s in it that
+/// are used with a `Double`.
+fn annotate_expr(expr: &mut Expr, annotations: &Annotations) {
+    match expr {
+        Expr::Integer(_, suffix @ None, span) | Expr::Float(_, suffix @ None, span) => {
+            *suffix = annotations.literals.get(&key(span)).cloned();
+        }
+        Expr::Interpolation(exprs, _)
+        | Expr::Array(exprs, _)
+        | Expr::FunctionCall {
+            parameters: exprs, ..
+        } => {
+            for expr in exprs {
+                annotate_expr(expr, annotations);
+            }
+        }
+        Expr::Object(fields, _) => {
+            for (_, value) in fields {
+                annotate_expr(value, annotations);
+            }
+        }
+        Expr::UnaryMinus(operand, _) | Expr::Not(operand, _) | Expr::BitNot(operand, _) => {
+            annotate_expr(operand, annotations)
+        }
+        Expr::Cast { value, .. } => annotate_expr(value, annotations),
+        Expr::ArrayIndex {
+            array: lhs,
+            index: rhs,
+            ..
+        }
+        | Expr::BinOp { lhs, rhs, .. } => {
+            annotate_expr(lhs, annotations);
+            annotate_expr(rhs, annotations);
+        }
+        _ => (),
+    }
+
+    let span = expr.span().clone();
+
+    if annotations
+        .widened
+        .contains(&(span.file.clone(), span.start, span.end))
+    {
+        let value = std::mem::replace(expr, Expr::TypeNone(span.clone()));
+        *expr = Expr::Cast {
+            value: Box::new(value),
+            r#type: Type::Double,
+            span,
+        };
+    }
+}
+
+/// Returns whether some statements contain a `return` of their own, rather than one of a function
+/// defined inside them.
+fn has_return(statements: &[Statement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::Return { .. } => true,
+        Statement::WhileLoop { body, .. } | Statement::ForLoop { body, .. } => has_return(body),
+        Statement::IfStatement {
+            body,
+            else_ifs,
+            else_body,
+            ..
+        } => {
+            has_return(body)
+                || else_ifs.iter().any(|(_, body)| has_return(body))
+                || has_return(else_body)
+        }
+        _ => false,
+    })
+}
+
+/// Returns whether running some statements always ends in a `return`. Loops might not run at
+/// all, so only an `if` with an `else` whose branches all return counts, besides a `return`.
+fn always_returns(statements: &[Statement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::Return { .. } => true,
+        Statement::IfStatement {
+            body,
+            else_ifs,
+            else_body,
+            ..
+        } => {
+            always_returns(body)
+                && else_ifs.iter().all(|(_, body)| always_returns(body))
+                && always_returns(else_body)
+        }
+        _ => false,
+    })
+}
+
+/// Finds the name most like a misspelt one, if any is close enough to be what was meant.
+fn closest<'b>(name: &str, candidates: impl Iterator<Item = &'b str>) -> Option<&'b str> {
+    // Allow roughly one mistake for every three characters.
+    let allowed = (name.chars().count() / 3).max(1);
+
+    candidates
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= allowed)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Counts the characters that must be inserted, removed or replaced to turn one string into
+/// another.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, b) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(a != *b);
+            current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// The names of the built-in types, which are the only types that exist.
+const TYPES: &[&str] = &[
+    "Float",
+    "Double",
+    "UnsignedInt",
+    "SignedInt",
+    "Long",
+    "char",
+    "bool",
+    "string",
+    "String",
+    "void",
+];
+
+/// Returns the name of the first type that doesn't exist in a type, if there is one.
+fn unknown(kind: &Type) -> Option<&str> {
+    match kind {
+        Type::Custom(name) => Some(name.as_str()),
+        Type::Array { kind, .. } => unknown(kind),
+        Type::Record(fields) => fields.iter().find_map(|(_, field)| unknown(field)),
+        _ => None,
+    }
+}
+
+/// Returns the type if it can be checked, which it can't if it names a type that doesn't exist.
+fn known(kind: &Type) -> Option<Type> {
+    match kind {
+        Type::Custom(_) => None,
+        Type::Array { kind: inner, .. } => known(inner).map(|_| kind.clone()),
+        Type::Record(fields) => fields
+            .iter()
+            .all(|(_, field)| known(field).is_some())
+            .then(|| kind.clone()),
+        _ => Some(kind.clone()),
+    }
+}
+
+/// Returns whether a type is or contains `void`, which values can't have, such as `void[2]`.
+fn contains_void(kind: &Type) -> bool {
+    match kind {
+        Type::Void => true,
+        Type::Array { kind, .. } => contains_void(kind),
+        Type::Record(fields) => fields.iter().any(|(_, field)| contains_void(field)),
+        _ => false,
+    }
+}
+
+fn is_integer(kind: &Type) -> bool {
+    matches!(kind, Type::SignedInt | Type::Long | Type::UnsignedInt)
+}
+
+fn is_float(kind: &Type) -> bool {
+    matches!(kind, Type::Float | Type::Double)
+}
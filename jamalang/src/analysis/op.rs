@@ -0,0 +1,157 @@
+use crate::{
+    diagnostic::{Diagnostic, ErrorCode, Span},
+    parser::{ast::Type, expr::Op},
+};
+
+use super::{is_float, is_integer, Checker};
+
+impl Checker<'_> {
+    /// Works out the type of a binary operation, given the types of its operands once literals have
+    /// taken their types and any `Float` used with a `Double` has been widened. Any other mix of
+    /// types is an error. A range gives the type of its elements.
+    pub(super) fn binary_op(
+        &mut self,
+        op: Op,
+        lhs: Option<Type>,
+        rhs: Option<Type>,
+        span: &Span,
+    ) -> Option<Type> {
+        let (Some(lhs), Some(rhs)) = (lhs, rhs) else {
+            return op.is_comparison().then_some(Type::Bool);
+        };
+
+        if lhs != Type::Void && rhs != Type::Void && lhs != rhs {
+            let message = if op.is_comparison() {
+                format!("cannot compare `{}` with `{}`", lhs, rhs)
+            } else {
+                format!("cannot use `{}` on `{}` and `{}`", op, lhs, rhs)
+            };
+            self.report(
+                Diagnostic::new(ErrorCode::TypeMismatch, message)
+                    .with_label(span.clone(), "these values have different types"),
+            );
+
+            // Carry on with a value of the type the operator would have produced.
+            return Some(if op.is_comparison() { Type::Bool } else { lhs });
+        }
+
+        let kind = &lhs;
+        let result = match op {
+            Op::Add if *kind == Type::String => Some(kind.clone()),
+            Op::Add | Op::Subtract | Op::Multiply | Op::Divide | Op::Modulo | Op::Power
+                if is_integer(kind) || is_float(kind) =>
+            {
+                Some(kind.clone())
+            }
+            Op::BitAnd | Op::BitOr | Op::BitXor | Op::ShiftLeft | Op::ShiftRight
+                if is_integer(kind) =>
+            {
+                Some(kind.clone())
+            }
+            Op::Lt | Op::Gt | Op::Lte | Op::Gte
+                if is_integer(kind) || is_float(kind) || *kind == Type::Char =>
+            {
+                Some(Type::Bool)
+            }
+            Op::Eq | Op::NotEq if *kind != Type::Void && rhs != Type::Void => Some(Type::Bool),
+            Op::And | Op::Or if *kind == Type::Bool => Some(Type::Bool),
+            Op::Range
+                if matches!(
+                    kind,
+                    Type::Float | Type::Double | Type::UnsignedInt | Type::SignedInt | Type::Long
+                ) =>
+            {
+                Some(kind.clone())
+            }
+            _ => None,
+        };
+
+        if result.is_none() {
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::TypeMismatch,
+                    format!("cannot use `{}` on `{}`", op, kind),
+                )
+                .with_label(span.clone(), format!("`{}` doesn't work on this type", op)),
+            );
+
+            // A `void` result would be reported again wherever it is used.
+            return match op.is_comparison() {
+                true => Some(Type::Bool),
+                false => (*kind != Type::Void).then_some(lhs),
+            };
+        }
+
+        result
+    }
+
+    pub(super) fn negate(&mut self, kind: Type, span: &Span) -> Option<Type> {
+        match kind {
+            Type::SignedInt | Type::Long | Type::Float | Type::Double => {}
+            Type::UnsignedInt => self.report(
+                Diagnostic::new(ErrorCode::TypeMismatch, "cannot negate `UnsignedInt`")
+                    .with_label(span.clone(), "unsigned integers can't be negative"),
+            ),
+            ref found => self.report(
+                Diagnostic::new(
+                    ErrorCode::TypeMismatch,
+                    format!("cannot negate `{}`", found),
+                )
+                .with_label(span.clone(), "only numbers can be negated"),
+            ),
+        }
+
+        Some(kind)
+    }
+
+    pub(super) fn not(&mut self, kind: Type, span: &Span) -> Option<Type> {
+        if kind != Type::Bool {
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::TypeMismatch,
+                    format!("cannot use `not` on `{}`", kind),
+                )
+                .with_label(span.clone(), "`not` only works on `bool`s"),
+            );
+        }
+
+        Some(Type::Bool)
+    }
+
+    pub(super) fn bit_not(&mut self, kind: Type, span: &Span) -> Option<Type> {
+        if !is_integer(&kind) {
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::TypeMismatch,
+                    format!("cannot use `~` on `{}`", kind),
+                )
+                .with_label(span.clone(), "`~` only works on integers"),
+            );
+        }
+
+        Some(kind)
+    }
+
+    /// Works out the type of a cast such as `x as Long`. Numbers, chars and bools can be cast to
+    /// each other, and anything can be cast to its own type.
+    pub(super) fn cast(&mut self, kind: Option<Type>, target: Type, span: &Span) -> Type {
+        let Some(kind) = kind else {
+            return target;
+        };
+        let castable = |kind: &Type| {
+            is_integer(kind) || is_float(kind) || matches!(kind, Type::Char | Type::Bool)
+        };
+
+        if kind != target && (!castable(&kind) || !castable(&target)) {
+            self.report(
+                Diagnostic::new(
+                    ErrorCode::InvalidCast,
+                    format!("cannot cast `{}` to `{}`", kind, target),
+                )
+                .with_label(span.clone(), "only numbers, chars and bools can be cast"),
+            );
+        }
+
+        target
+    }
+}
@@ -1,12 +1,45 @@
-use std::ffi::{c_void, CStr};
+use std::ffi::{c_char, c_void, CStr, CString};
 
-use inkwell::{context::Context, types::FunctionType};
+use inkwell::{context::Context, types::FunctionType, AddressSpace};
+
+pub(crate) const STRING_CONCAT: &str = "jamalang_internal$string_concat";
+pub(crate) const STRING_FREE: &str = "jamalang_internal$string_free";
+pub(crate) const STRING_EQUALS: &str = "jamalang_internal$string_equals";
+pub(crate) const NUMBER_TO_STRING: &str = "jamalang_internal$number_to_string";
+pub(crate) const DOUBLE_TO_STRING: &str = "jamalang_internal$double_to_string";
+pub(crate) const SIGNED_TO_STRING: &str = "jamalang_internal$signed_to_string";
+pub(crate) const UNSIGNED_TO_STRING: &str = "jamalang_internal$unsigned_to_string";
+pub(crate) const INTEGER_POWER: &str = "jamalang_internal$integer_power";
+pub(crate) const CHAR_TO_STRING: &str = "jamalang_internal$char_to_string";
+pub(crate) const BOOL_TO_STRING: &str = "jamalang_internal$bool_to_string";
+pub(crate) const INDEX_OUT_OF_BOUNDS: &str = "jamalang_internal$index_out_of_bounds";
+pub(crate) const DIVISION_BY_ZERO: &str = "jamalang_internal$division_by_zero";
 
 #[no_mangle]
 pub extern "C" fn builtin_println_number(x: f32) {
     println!("{}", x);
 }
 
+#[no_mangle]
+pub extern "C" fn builtin_println_double(x: f64) {
+    println!("{}", x);
+}
+
+#[no_mangle]
+pub extern "C" fn builtin_println_unsigned_int(x: u32) {
+    println!("{}", x);
+}
+
+#[no_mangle]
+pub extern "C" fn builtin_println_signed_int(x: i32) {
+    println!("{}", x);
+}
+
+#[no_mangle]
+pub extern "C" fn builtin_println_long(x: i64) {
+    println!("{}", x);
+}
+
 #[no_mangle]
 pub extern "C" fn builtin_println_char(x: i8) {
     println!("{}", (x as u8) as char);
@@ -17,6 +50,134 @@ pub extern "C" fn builtin_println_bool(x: bool) {
     println!("{}", x);
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn builtin_println_string(x: *const c_char) {
+    println!("{}", CStr::from_ptr(x).to_string_lossy());
+}
+
+/// Joins `count` strings into a new one. Its length is worked out first, so it is allocated once.
+#[no_mangle]
+pub unsafe extern "C" fn builtin_string_concat(
+    parts: *const *const c_char,
+    count: u64,
+) -> *mut c_char {
+    let parts = std::slice::from_raw_parts(parts, count as usize);
+    let len = parts
+        .iter()
+        .map(|part| CStr::from_ptr(*part).to_bytes().len())
+        .sum::<usize>();
+    let mut bytes = Vec::with_capacity(len + 1);
+
+    for part in parts {
+        bytes.extend_from_slice(CStr::from_ptr(*part).to_bytes());
+    }
+
+    // The parts are C strings, so none of them contain a null byte.
+    CString::from_vec_unchecked(bytes).into_raw()
+}
+
+/// Frees a string created by one of the other builtins.
+#[no_mangle]
+pub unsafe extern "C" fn builtin_string_free(string: *mut c_char) {
+    drop(CString::from_raw(string));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn builtin_string_equals(a: *const c_char, b: *const c_char) -> bool {
+    CStr::from_ptr(a) == CStr::from_ptr(b)
+}
+
+/// Converts a value into a new string with the same formatting as `println`.
+fn to_string(value: impl std::fmt::Display) -> *mut c_char {
+    CString::new(value.to_string())
+        .expect("strings should not contain null bytes")
+        .into_raw()
+}
+
+#[no_mangle]
+pub extern "C" fn builtin_number_to_string(x: f32) -> *mut c_char {
+    to_string(x)
+}
+
+#[no_mangle]
+pub extern "C" fn builtin_double_to_string(x: f64) -> *mut c_char {
+    to_string(x)
+}
+
+/// Converts any signed integer, sign extended to 64 bits.
+#[no_mangle]
+pub extern "C" fn builtin_signed_to_string(x: i64) -> *mut c_char {
+    to_string(x)
+}
+
+/// Converts an unsigned integer, zero extended to 64 bits.
+#[no_mangle]
+pub extern "C" fn builtin_unsigned_to_string(x: u64) -> *mut c_char {
+    to_string(x)
+}
+
+/// Raises an integer to a power, wrapping around on overflow. A negative exponent gives the
+/// truncated result of dividing 1 by the base to the opposite power, which is 0 unless the base is
+/// 1 or -1. Codegen stops the program before a base of 0 is raised to one.
+#[no_mangle]
+pub extern "C" fn builtin_integer_power(base: i64, exponent: i64) -> i64 {
+    if exponent < 0 {
+        return match base {
+            1 => 1,
+            -1 if exponent % 2 == 0 => 1,
+            -1 => -1,
+            _ => 0,
+        };
+    }
+
+    let (mut result, mut base, mut exponent) = (1i64, base, exponent as u64);
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result.wrapping_mul(base);
+        }
+        base = base.wrapping_mul(base);
+        exponent >>= 1;
+    }
+
+    result
+}
+
+#[no_mangle]
+pub extern "C" fn builtin_char_to_string(x: i8) -> *mut c_char {
+    to_string((x as u8) as char)
+}
+
+#[no_mangle]
+pub extern "C" fn builtin_bool_to_string(x: bool) -> *mut c_char {
+    to_string(x)
+}
+
+/// Reports an array access outside of the array, and stops the program.
+#[no_mangle]
+pub unsafe extern "C" fn builtin_index_out_of_bounds(
+    location: *const c_char,
+    index: i64,
+    len: i64,
+) {
+    eprintln!(
+        "runtime error at {}: index {} is out of bounds for an array of length {}",
+        CStr::from_ptr(location).to_string_lossy(),
+        index,
+        len
+    );
+    std::process::exit(101);
+}
+
+/// Reports an integer divided by zero, or the remainder of one, and stops the program.
+#[no_mangle]
+pub unsafe extern "C" fn builtin_division_by_zero(location: *const c_char) {
+    eprintln!(
+        "runtime error at {}: division by zero",
+        CStr::from_ptr(location).to_string_lossy()
+    );
+    std::process::exit(101);
+}
+
 #[derive(Debug)]
 pub struct Builtin<'a> {
     pub name: String,
@@ -32,6 +193,8 @@ macro_rules! cstr {
 }
 
 pub fn builtins<'a>(context: &'a Context) -> Vec<Builtin<'a>> {
+    let string_type = context.i8_type().ptr_type(AddressSpace::default());
+
     unsafe {
         vec![
             Builtin {
@@ -42,6 +205,38 @@ pub fn builtins<'a>(context: &'a Context) -> Vec<Builtin<'a>> {
                     .void_type()
                     .fn_type(&[context.f32_type().into()], false),
             },
+            Builtin {
+                name: "println_d".to_string(),
+                c_name: cstr!("builtin_println_double"),
+                function: builtin_println_double as *mut c_void,
+                f_type: context
+                    .void_type()
+                    .fn_type(&[context.f64_type().into()], false),
+            },
+            Builtin {
+                name: "println_u".to_string(),
+                c_name: cstr!("builtin_println_unsigned_int"),
+                function: builtin_println_unsigned_int as *mut c_void,
+                f_type: context
+                    .void_type()
+                    .fn_type(&[context.i32_type().into()], false),
+            },
+            Builtin {
+                name: "println_i".to_string(),
+                c_name: cstr!("builtin_println_signed_int"),
+                function: builtin_println_signed_int as *mut c_void,
+                f_type: context
+                    .void_type()
+                    .fn_type(&[context.i32_type().into()], false),
+            },
+            Builtin {
+                name: "println_l".to_string(),
+                c_name: cstr!("builtin_println_long"),
+                function: builtin_println_long as *mut c_void,
+                f_type: context
+                    .void_type()
+                    .fn_type(&[context.i64_type().into()], false),
+            },
             Builtin {
                 name: "println_c".to_string(),
                 c_name: cstr!("builtin_println_char"),
@@ -58,6 +253,102 @@ pub fn builtins<'a>(context: &'a Context) -> Vec<Builtin<'a>> {
                     .void_type()
                     .fn_type(&[context.bool_type().into()], false),
             },
+            Builtin {
+                name: "println_s".to_string(),
+                c_name: cstr!("builtin_println_string"),
+                function: builtin_println_string as *mut c_void,
+                f_type: context.void_type().fn_type(&[string_type.into()], false),
+            },
+            Builtin {
+                name: STRING_CONCAT.to_string(),
+                c_name: cstr!("builtin_string_concat"),
+                function: builtin_string_concat as *mut c_void,
+                f_type: string_type.fn_type(
+                    &[
+                        string_type.ptr_type(AddressSpace::default()).into(),
+                        context.i64_type().into(),
+                    ],
+                    false,
+                ),
+            },
+            Builtin {
+                name: STRING_FREE.to_string(),
+                c_name: cstr!("builtin_string_free"),
+                function: builtin_string_free as *mut c_void,
+                f_type: context.void_type().fn_type(&[string_type.into()], false),
+            },
+            Builtin {
+                name: STRING_EQUALS.to_string(),
+                c_name: cstr!("builtin_string_equals"),
+                function: builtin_string_equals as *mut c_void,
+                f_type: context
+                    .bool_type()
+                    .fn_type(&[string_type.into(), string_type.into()], false),
+            },
+            Builtin {
+                name: NUMBER_TO_STRING.to_string(),
+                c_name: cstr!("builtin_number_to_string"),
+                function: builtin_number_to_string as *mut c_void,
+                f_type: string_type.fn_type(&[context.f32_type().into()], false),
+            },
+            Builtin {
+                name: DOUBLE_TO_STRING.to_string(),
+                c_name: cstr!("builtin_double_to_string"),
+                function: builtin_double_to_string as *mut c_void,
+                f_type: string_type.fn_type(&[context.f64_type().into()], false),
+            },
+            Builtin {
+                name: SIGNED_TO_STRING.to_string(),
+                c_name: cstr!("builtin_signed_to_string"),
+                function: builtin_signed_to_string as *mut c_void,
+                f_type: string_type.fn_type(&[context.i64_type().into()], false),
+            },
+            Builtin {
+                name: UNSIGNED_TO_STRING.to_string(),
+                c_name: cstr!("builtin_unsigned_to_string"),
+                function: builtin_unsigned_to_string as *mut c_void,
+                f_type: string_type.fn_type(&[context.i64_type().into()], false),
+            },
+            Builtin {
+                name: INTEGER_POWER.to_string(),
+                c_name: cstr!("builtin_integer_power"),
+                function: builtin_integer_power as *mut c_void,
+                f_type: context.i64_type().fn_type(
+                    &[context.i64_type().into(), context.i64_type().into()],
+                    false,
+                ),
+            },
+            Builtin {
+                name: CHAR_TO_STRING.to_string(),
+                c_name: cstr!("builtin_char_to_string"),
+                function: builtin_char_to_string as *mut c_void,
+                f_type: string_type.fn_type(&[context.i8_type().into()], false),
+            },
+            Builtin {
+                name: BOOL_TO_STRING.to_string(),
+                c_name: cstr!("builtin_bool_to_string"),
+                function: builtin_bool_to_string as *mut c_void,
+                f_type: string_type.fn_type(&[context.bool_type().into()], false),
+            },
+            Builtin {
+                name: INDEX_OUT_OF_BOUNDS.to_string(),
+                c_name: cstr!("builtin_index_out_of_bounds"),
+                function: builtin_index_out_of_bounds as *mut c_void,
+                f_type: context.void_type().fn_type(
+                    &[
+                        string_type.into(),
+                        context.i64_type().into(),
+                        context.i64_type().into(),
+                    ],
+                    false,
+                ),
+            },
+            Builtin {
+                name: DIVISION_BY_ZERO.to_string(),
+                c_name: cstr!("builtin_division_by_zero"),
+                function: builtin_division_by_zero as *mut c_void,
+                f_type: context.void_type().fn_type(&[string_type.into()], false),
+            },
         ]
     }
 }
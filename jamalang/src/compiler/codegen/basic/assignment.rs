@@ -1,7 +1,13 @@
 use crate::parser::ast::{Assignment, AssignmentType};
-use inkwell::values::{BasicValue, BasicValueEnum};
+use inkwell::types::BasicTypeEnum;
 
-use crate::compiler::codegen::{get_variable, stack_top, value::Value, Codegen};
+use crate::compiler::codegen::{
+    check_type_hint,
+    expr::{binary_op, element_pointer, free, is_temporary},
+    get_pointer, stack_top,
+    value::Value,
+    Codegen,
+};
 
 impl<'a, 'ctx> Codegen<'a, 'ctx> for Assignment {
     fn codegen(
@@ -21,131 +27,103 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for Assignment {
             >,
         >,
     ) -> Value<'ctx> {
+        let temporary = is_temporary(&self.value);
         let ass_value =
             self.value
                 .codegen(compiler, main_function, context, module, builder, variables);
-        if let Some(index) = self.index {
-            let index = index.codegen(compiler, main_function, context, module, builder, variables);
-            match self.r#type {
-                AssignmentType::Set => {
-                    let (array, array_ptr) = get_variable(variables, &self.identifier);
-
-                    if let Value::Float(index) = index {
-                        let ptr = unsafe {
-                            builder.build_gep(
-                                array.into_array_value().get_type().get_element_type(),
-                                *array_ptr,
-                                &[index.const_to_unsigned_int(context.i32_type())],
-                                "array_extract",
-                            )
-                        };
 
-                        builder.build_store(ptr, ass_value.into_basic_value());
-                    } else {
-                        panic!("Index is not a number")
-                    }
-                }
-                _ => unreachable!("Cannot yet use {:?} on arrays", self.r#type),
-            }
-        } else {
-            match self.r#type {
-                AssignmentType::Set => {
-                    let ptr = if main_function {
-                        let global = ass_value.global_alloca(context, module, &self.identifier);
-                        global.set_initializer(&ass_value.into_basic_value());
-                        global.as_pointer_value()
-                    } else {
-                        let ptr = ass_value.alloca(context, builder, &self.identifier);
-                        builder.build_store(ptr, ass_value.into_basic_value());
-                        ptr
-                    };
+        if let Some(hint) = &self.type_hint {
+            check_type_hint(
+                compiler,
+                hint,
+                ass_value.into_basic_value().get_type(),
+                &self.span,
+            );
+        }
 
-                    stack_top!(variables)
-                        .insert(self.identifier, (ass_value.into_basic_value(), ptr));
-                }
-                AssignmentType::Add => {
-                    let (ty, ptr) = get_variable(variables, &self.identifier);
-                    let current_value = builder.build_load(
-                        ty.as_basic_value_enum().get_type(),
-                        *ptr,
-                        &format!("load_{}", self.identifier),
-                    );
+        if self.r#type == AssignmentType::Set
+            && self.indices.is_empty()
+            && !self.identifier.contains('.')
+        {
+            let ptr = if main_function {
+                // Top-level code runs inside a function, so the value doesn't need to be a
+                // constant.
+                let global =
+                    ass_value.global_alloca(context, module, &compiler.mangle(&self.identifier));
+                let value = ass_value.into_basic_value();
+                global.set_initializer(&value.get_type().const_zero());
+                builder.build_store(global.as_pointer_value(), value);
+                global.as_pointer_value()
+            } else {
+                let ptr = ass_value.alloca(context, builder, &self.identifier);
+                builder.build_store(ptr, ass_value.into_basic_value());
+                ptr
+            };
 
-                    let result = match (current_value, ass_value) {
-                        (BasicValueEnum::FloatValue(current_value), Value::Float(ass_value)) => {
-                            builder.build_float_add(current_value, ass_value, "add")
-                        }
-                        _ => unreachable!(
-                            "Cannot yet add-assign {:?} with {:?}",
-                            current_value, ass_value
-                        ),
-                    };
+            stack_top!(variables).insert(self.identifier, (ass_value.into_basic_value(), ptr));
+            return Value::Void;
+        }
 
-                    builder.build_store(*ptr, result);
-                }
-                AssignmentType::Sub => {
-                    let (ty, ptr) = get_variable(variables, &self.identifier);
-                    let current_value =
-                        builder.build_load(ty.as_basic_value_enum().get_type(), *ptr, "load");
+        // Everything else assigns to something that already exists: a variable, a field of a
+        // record (or a variable of an imported file), or an element of an array.
+        let (mut ty, mut ptr) =
+            get_pointer(compiler, builder, variables, &self.identifier, &self.span).unwrap_or_else(
+                || {
+                    panic!(
+                        "{}: Attempted to access unknown variable {}",
+                        self.span, self.identifier
+                    )
+                },
+            );
 
-                    let result = match (current_value, ass_value) {
-                        (BasicValueEnum::FloatValue(current_value), Value::Float(ass_value)) => {
-                            builder.build_float_sub(current_value, ass_value, "sub")
-                        }
-                        _ => unreachable!(
-                            "Cannot yet sub-assign {:?} with {:?}",
-                            current_value, ass_value
-                        ),
-                    };
+        for index in self.indices {
+            let BasicTypeEnum::ArrayType(array_type) = ty else {
+                panic!("{}: Cannot index into {}", self.span, self.identifier)
+            };
 
-                    builder.build_store(*ptr, result);
-                }
-                AssignmentType::Mul => {
-                    let (ty, ptr) = get_variable(variables, &self.identifier);
-                    let current_value = builder.build_load(
-                        ty.as_basic_value_enum().get_type(),
-                        *ptr,
-                        &format!("load_{}", self.identifier),
-                    );
+            let index = index.codegen(compiler, main_function, context, module, builder, variables);
+            ptr = element_pointer(
+                compiler, module, builder, array_type, ptr, index, &self.span,
+            );
+            ty = array_type.get_element_type();
+        }
 
-                    let result = match (current_value, ass_value) {
-                        (BasicValueEnum::FloatValue(current_value), Value::Float(ass_value)) => {
-                            builder.build_float_mul(current_value, ass_value, "mul")
-                        }
-                        _ => unreachable!(
-                            "Cannot yet mul-assign {:?} with {:?}",
-                            current_value, ass_value
-                        ),
-                    };
+        let value = match self.r#type.operator() {
+            Some(op) => {
+                let current = builder.build_load(ty, ptr, &format!("load_{}", self.identifier));
+                let value = binary_op(
+                    compiler,
+                    module,
+                    builder,
+                    op,
+                    current.into(),
+                    ass_value,
+                    &self.span,
+                );
 
-                    builder.build_store(*ptr, result);
+                // `s += "{x}"` only needs the new string until it has been joined onto `s`.
+                if let (Value::String(string), true) = (ass_value, temporary) {
+                    free(module, builder, string);
                 }
-                AssignmentType::Div => {
-                    let (ty, ptr) = get_variable(variables, &self.identifier);
-                    let current_value = builder.build_load(
-                        ty.as_basic_value_enum().get_type(),
-                        *ptr,
-                        &format!("load_{}", self.identifier),
-                    );
 
-                    let result = match (current_value, ass_value) {
-                        (BasicValueEnum::FloatValue(current_value), Value::Float(ass_value)) => {
-                            builder.build_float_div(current_value, ass_value, "add")
-                        }
-                        _ => unreachable!(
-                            "Cannot yet div-assign {:?} with {:?}",
-                            current_value, ass_value
-                        ),
-                    };
+                value
+            }
+            None => ass_value,
+        }
+        .into_basic_value();
 
-                    builder.build_store(*ptr, result);
-                }
-                AssignmentType::Pow => {
-                    todo!()
-                }
-            };
+        if value.get_type() != ty {
+            panic!(
+                "{}: Cannot assign a value of type {} to {}, which has type {}",
+                self.span,
+                value.get_type().print_to_string().to_string_lossy(),
+                self.identifier,
+                ty.print_to_string().to_string_lossy()
+            );
         }
 
+        builder.build_store(ptr, value);
+
         Value::Void
     }
 }
@@ -1,12 +1,16 @@
-use crate::parser::ast::ForeignModule;
+use crate::parser::{ast::ForeignModule, imports::namespace};
+use inkwell::{module::Linkage, values::BasicValue};
 
-use crate::compiler::codegen::{value::Value, Codegen};
+use crate::compiler::{
+    codegen::{stack_top, value::Value, Codegen},
+    initializer_name, Compiler, Exports,
+};
 
 impl<'a, 'ctx> Codegen<'a, 'ctx> for ForeignModule {
     fn codegen(
         self,
         compiler: &'a crate::compiler::Compiler<'a, 'ctx>,
-        main_function: bool,
+        _main_function: bool,
         context: &'ctx inkwell::context::Context,
         module: &'a inkwell::module::Module<'ctx>,
         builder: &'a inkwell::builder::Builder<'ctx>,
@@ -20,11 +24,102 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for ForeignModule {
             >,
         >,
     ) -> Value<'ctx> {
-        //TODO: These should be compiled into a separate module
-        for statement in self.0.statements {
-            statement.codegen(compiler, main_function, context, module, builder, variables);
+        // Files that were already imported elsewhere have no body, and only need their symbols
+        // declaring in this module.
+        let exports = match self.file {
+            Some(file) => {
+                let namespace = namespace(&self.path);
+                let sub_module = context.create_module(&namespace);
+                let sub_builder = context.create_builder();
+                let mut sub_compiler = Compiler::new(context, &sub_module, &sub_builder);
+                sub_compiler.namespace = Some(namespace.clone());
+                sub_compiler.bounds_checks = compiler.bounds_checks;
+                sub_compiler.imports = compiler.imports.clone();
+                sub_compiler.records = compiler.records.clone();
+
+                let result = sub_compiler.compile_import(file);
+                let prefix = format!("{}.", namespace);
+                let exports = Exports {
+                    functions: sub_module
+                        .get_functions()
+                        .filter(|function| function.count_basic_blocks() > 0)
+                        .map(|function| {
+                            (
+                                function.get_name().to_string_lossy().into_owned(),
+                                function.get_type(),
+                            )
+                        })
+                        .filter(|(name, _)| name.starts_with(&prefix))
+                        .collect(),
+                    globals: result
+                        .variables
+                        .into_iter()
+                        // Leave out the variables of files imported by the imported file.
+                        .filter(|(identifier, _)| !identifier.contains('.'))
+                        .map(|(identifier, (value, ptr))| {
+                            (
+                                format!("{}{}", prefix, identifier),
+                                value.get_type(),
+                                ptr.get_name().to_string_lossy().into_owned(),
+                            )
+                        })
+                        .collect(),
+                    namespace,
+                };
+
+                drop(sub_compiler);
+                module
+                    .link_in_module(sub_module)
+                    .unwrap_or_else(|error| panic!("{}: {}", self.span, error));
+                compiler
+                    .imports
+                    .borrow_mut()
+                    .exports
+                    .insert(self.path, exports.clone());
+
+                exports
+            }
+            None => compiler
+                .imports
+                .borrow()
+                .exports
+                .get(&self.path)
+                .cloned()
+                .unwrap_or_else(|| panic!("{}: import should already be compiled", self.span)),
+        };
+
+        for (name, f_type) in &exports.functions {
+            if module.get_function(name).is_none() {
+                module.add_function(name, *f_type, Some(Linkage::External));
+            }
+        }
+
+        for (name, ty, symbol) in exports.globals {
+            let global = module.get_global(&symbol).unwrap_or_else(|| {
+                let global = module.add_global(ty, None, &symbol);
+                global.set_linkage(Linkage::External);
+                global
+            });
+
+            stack_top!(variables).insert(
+                name,
+                (
+                    ty.const_zero().as_basic_value_enum(),
+                    global.as_pointer_value(),
+                ),
+            );
         }
 
+        let init = initializer_name(&exports.namespace);
+        let init = module.get_function(&init).unwrap_or_else(|| {
+            module.add_function(
+                &init,
+                context.void_type().fn_type(&[], false),
+                Some(Linkage::External),
+            )
+        });
+        builder.build_call(init, &[], "");
+
         Value::Void
     }
 }
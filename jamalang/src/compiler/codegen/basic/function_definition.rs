@@ -1,12 +1,8 @@
 use std::collections::HashMap;
 
-use crate::parser::ast::FunctionDefinition;
-use inkwell::types::BasicType;
+use crate::parser::ast::{FunctionDefinition, Type};
 
-use crate::compiler::codegen::{
-    get_any_type_from_type_hint, get_basic_type_metadata_from_type_hint, stack_top, value::Value,
-    Codegen,
-};
+use crate::compiler::codegen::{stack_top, value::Value, Codegen};
 
 impl<'a, 'ctx> Codegen<'a, 'ctx> for FunctionDefinition {
     fn codegen(
@@ -26,17 +22,20 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for FunctionDefinition {
             >,
         >,
     ) -> Value<'ctx> {
-        let return_type = get_any_type_from_type_hint(context, self.return_type_hint);
-        let fn_type = return_type.fn_type(
-            self.parameters
-                .iter()
-                .map(|p| get_basic_type_metadata_from_type_hint(context, p.1.clone()))
-                .collect::<Vec<_>>()
-                .as_slice(),
-            false,
-        );
-
-        let function = module.add_function(self.identifier.as_str(), fn_type, None);
+        // Functions are declared before any code is generated, so that they can be called before
+        // they are defined. A name the standard library already defines gets a function of its own.
+        let name = compiler.mangle(&self.identifier);
+        let return_type = self
+            .return_type_hint
+            .expect("return types should be inferred before codegen");
+        let function = match module.get_function(&name) {
+            Some(function) if function.count_basic_blocks() == 0 => function,
+            _ => module.add_function(
+                &name,
+                compiler.function_type(&self.parameters, &return_type),
+                None,
+            ),
+        };
         let fn_builder = context.create_builder();
         fn_builder.position_at_end(context.append_basic_block(function, self.identifier.as_str()));
         variables.push(HashMap::new());
@@ -67,7 +66,13 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for FunctionDefinition {
             .get_terminator()
             .is_none()
         {
-            fn_builder.build_return(None);
+            // Every path through a function that returns a value ends in a `return`, so the block
+            // left over is the unreachable one after an `if` whose branches all return.
+            if return_type == Type::Void {
+                fn_builder.build_return(None);
+            } else {
+                fn_builder.build_unreachable();
+            }
         }
 
         variables.pop();
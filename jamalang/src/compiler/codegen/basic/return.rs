@@ -23,7 +23,22 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for Return {
         let value =
             self.value
                 .codegen(compiler, main_function, context, module, builder, variables);
-        builder.build_return(Some(&value.into_basic_value()));
+        let return_type = builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent())
+            .expect("return statements should be inside a function")
+            .get_type()
+            .get_return_type();
+        match return_type {
+            Some(_) => {
+                builder.build_return(Some(&value.into_basic_value()));
+            }
+            // Only `void` can be returned from a function without a return type.
+            None => {
+                builder.build_return(None);
+            }
+        }
+
         Value::Void
     }
 }
@@ -1,6 +1,26 @@
-use crate::parser::expr::{Array, ArrayIndex};
+use std::collections::HashMap;
 
-use crate::compiler::codegen::{value::Value, Codegen};
+use crate::diagnostic::Span;
+use crate::parser::expr::{Array, ArrayIndex, Expr};
+use inkwell::{
+    builder::Builder,
+    context::Context,
+    module::Module,
+    types::{ArrayType, BasicType, BasicTypeEnum},
+    values::{ArrayValue, AsValueRef, BasicValueEnum, PointerValue},
+    IntPredicate,
+};
+use llvm_sys::core::LLVMIsConstant;
+
+use crate::compiler::{
+    builtins::INDEX_OUT_OF_BOUNDS,
+    codegen::{
+        entry_alloca, get_pointer,
+        value::{unwrap_unsigned, Value},
+        Codegen,
+    },
+    Compiler,
+};
 
 impl<'a, 'ctx> Codegen<'a, 'ctx> for Array {
     fn codegen(
@@ -22,41 +42,90 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for Array {
     ) -> Value<'ctx> {
         let mut values = vec![];
         for expr in self.0 {
-            values.push(expr.codegen(compiler, main_function, context, module, builder, variables));
+            let value = expr.codegen(compiler, main_function, context, module, builder, variables);
+            values.push(match value {
+                Value::Void => panic!("{}: Arrays cannot contain void", self.1),
+                value => value,
+            });
         }
 
-        let array = match values
+        let element_type = values
             .first()
-            .expect("array should have at least one element")
+            .and_then(Value::get_type)
+            .unwrap_or_else(|| panic!("{}: array should have at least one element", self.1));
+        let values = values
+            .into_iter()
+            .map(Value::into_basic_value)
+            .collect::<Vec<_>>();
+
+        if values
+            .iter()
+            .all(|value| unsafe { LLVMIsConstant(value.as_value_ref()) } == 1)
         {
-            Value::Float(float_value) => float_value.get_type().const_array(
-                values
-                    .iter()
-                    .map(|value| value.into_basic_value().into_float_value())
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            ),
-            Value::Char(char_value) => char_value.get_type().const_array(
-                values
-                    .iter()
-                    .map(|value| value.into_basic_value().into_int_value())
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            ),
-            Value::Bool(bool_value) => bool_value.get_type().const_array(
-                values
-                    .iter()
-                    .map(|value| value.into_basic_value().into_int_value())
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            ),
-            _ => unreachable!("Variable type not yet supported in arrays"),
-        };
+            return Value::Array(const_array(element_type, &values));
+        }
+
+        // Elements computed at runtime are inserted one by one, which needs no stack space.
+        let array_type = element_type.array_type(values.len() as u32);
+        let array =
+            values
+                .into_iter()
+                .enumerate()
+                .fold(array_type.get_undef(), |array, (index, value)| {
+                    builder
+                        .build_insert_value(array, value, index as u32, "array_element")
+                        .expect("element index should be within the array")
+                        .into_array_value()
+                });
 
         Value::Array(array)
     }
 }
 
+fn const_array<'ctx>(
+    element_type: BasicTypeEnum<'ctx>,
+    values: &[BasicValueEnum<'ctx>],
+) -> ArrayValue<'ctx> {
+    match element_type {
+        BasicTypeEnum::ArrayType(array_type) => array_type.const_array(
+            &values
+                .iter()
+                .map(|value| value.into_array_value())
+                .collect::<Vec<_>>(),
+        ),
+        BasicTypeEnum::FloatType(float_type) => float_type.const_array(
+            &values
+                .iter()
+                .map(|value| value.into_float_value())
+                .collect::<Vec<_>>(),
+        ),
+        BasicTypeEnum::IntType(int_type) => int_type.const_array(
+            &values
+                .iter()
+                .map(|value| value.into_int_value())
+                .collect::<Vec<_>>(),
+        ),
+        BasicTypeEnum::PointerType(pointer_type) => pointer_type.const_array(
+            &values
+                .iter()
+                .map(|value| value.into_pointer_value())
+                .collect::<Vec<_>>(),
+        ),
+        BasicTypeEnum::StructType(struct_type) => struct_type.const_array(
+            &values
+                .iter()
+                .map(|value| value.into_struct_value())
+                .collect::<Vec<_>>(),
+        ),
+        BasicTypeEnum::VectorType(vector_type) => vector_type.const_array(
+            &values
+                .iter()
+                .map(|value| value.into_vector_value())
+                .collect::<Vec<_>>(),
+        ),
+    }
+}
+
 impl<'a, 'ctx> Codegen<'a, 'ctx> for ArrayIndex {
     fn codegen(
         self,
@@ -75,37 +144,154 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for ArrayIndex {
             >,
         >,
     ) -> Value<'ctx> {
-        let array = self
-            .array
-            .codegen(compiler, main_function, context, module, builder, variables)
-            .into_basic_value();
+        let (ty, array_ptr) = if is_place(&self.array) {
+            place_pointer(
+                *self.array,
+                compiler,
+                main_function,
+                context,
+                module,
+                builder,
+                variables,
+            )
+        } else {
+            // Anything else, such as the result of a call, is stored somewhere first.
+            let array = self
+                .array
+                .codegen(compiler, main_function, context, module, builder, variables)
+                .into_basic_value();
+            let array_ptr = entry_alloca(context, builder, array.get_type(), "indexing_alloc");
+            builder.build_store(array_ptr, array);
+            (array.get_type(), array_ptr)
+        };
+        let BasicTypeEnum::ArrayType(array_type) = ty else {
+            panic!("{}: Cannot index into {:?}", self.span, ty)
+        };
 
         let index =
             self.index
                 .codegen(compiler, main_function, context, module, builder, variables);
+        let ptr = element_pointer(
+            compiler, module, builder, array_type, array_ptr, index, &self.span,
+        );
 
-        if let Value::Float(index) = index {
-            let array_ptr = builder.build_alloca(array.get_type(), "indexing_alloc");
-            builder.build_store(array_ptr, array);
+        builder
+            .build_load(array_type.get_element_type(), ptr, "indexing_load")
+            .into()
+    }
+}
+
+/// Returns whether an expression refers to a value stored somewhere, which is a variable, a field
+/// of a record stored in one, or an element of an array that does.
+fn is_place(expr: &Expr) -> bool {
+    match expr {
+        Expr::Identifier(..) => true,
+        Expr::ArrayIndex { array, .. } => is_place(array),
+        _ => false,
+    }
+}
 
-            let ptr = unsafe {
-                builder.build_gep(
-                    array.into_array_value().get_type().get_element_type(),
-                    array_ptr,
-                    &[index.const_to_unsigned_int(context.i32_type())],
-                    "array_extract",
+/// Returns the type of the value an expression accepted by [`is_place`] refers to and a pointer to
+/// it, so that indexing into an array doesn't copy it.
+fn place_pointer<'a, 'ctx>(
+    expr: Expr,
+    compiler: &'a Compiler<'a, 'ctx>,
+    main_function: bool,
+    context: &'ctx Context,
+    module: &'a Module<'ctx>,
+    builder: &'a Builder<'ctx>,
+    variables: &'a mut Vec<HashMap<String, (BasicValueEnum<'ctx>, PointerValue<'ctx>)>>,
+) -> (BasicTypeEnum<'ctx>, PointerValue<'ctx>) {
+    match expr {
+        Expr::Identifier(identifier, span) => {
+            get_pointer(compiler, builder, variables, &identifier, &span).unwrap_or_else(|| {
+                panic!(
+                    "{}: Attempted to access unknown variable {}",
+                    span, identifier
                 )
+            })
+        }
+        Expr::ArrayIndex { array, index, span } => {
+            let (ty, array_ptr) = place_pointer(
+                *array,
+                compiler,
+                main_function,
+                context,
+                module,
+                builder,
+                variables,
+            );
+            let BasicTypeEnum::ArrayType(array_type) = ty else {
+                panic!("{}: Cannot index into {:?}", span, ty)
             };
+            let index = index.codegen(compiler, main_function, context, module, builder, variables);
+            let ptr = element_pointer(
+                compiler, module, builder, array_type, array_ptr, index, &span,
+            );
 
-            builder
-                .build_load(
-                    array.get_type().into_array_type().get_element_type(),
-                    ptr,
-                    "indexing_load",
-                )
-                .into()
-        } else {
-            panic!("Index is not a number")
+            (array_type.get_element_type(), ptr)
+        }
+        expr => unreachable!("{}: Only places have pointers", expr.span()),
+    }
+}
+
+/// Returns a pointer to an element of the array behind `array_ptr`. Unless bounds checks are
+/// turned off, the program stops with a runtime error if the index is outside of the array.
+pub(crate) fn element_pointer<'ctx>(
+    compiler: &Compiler<'_, 'ctx>,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    array_type: ArrayType<'ctx>,
+    array_ptr: PointerValue<'ctx>,
+    index: Value<'ctx>,
+    span: &Span,
+) -> PointerValue<'ctx> {
+    let context = compiler.context;
+    let index = match index {
+        Value::SignedInt(index) | Value::Long(index) => {
+            builder.build_int_s_extend_or_bit_cast(index, context.i64_type(), "index")
+        }
+        Value::UnsignedInt(index) => {
+            builder.build_int_z_extend(unwrap_unsigned(builder, index), context.i64_type(), "index")
         }
+        _ => unreachable!("{}: Cannot index with {:#?}", span, index),
+    };
+
+    if compiler.bounds_checks {
+        let len = context.i64_type().const_int(array_type.len() as u64, false);
+        // Negative indices wrap around to huge unsigned ones, so one comparison covers both ends.
+        let in_bounds = builder.build_int_compare(IntPredicate::ULT, index, len, "in_bounds");
+
+        let function = builder
+            .get_insert_block()
+            .and_then(|block| block.get_parent())
+            .expect("function should be present");
+        let out_of_bounds_block = context.append_basic_block(function, "out_of_bounds");
+        let in_bounds_block = context.append_basic_block(function, "in_bounds");
+        builder.build_conditional_branch(in_bounds, in_bounds_block, out_of_bounds_block);
+
+        builder.position_at_end(out_of_bounds_block);
+        let location = builder
+            .build_global_string_ptr(&span.to_string(), "location")
+            .as_pointer_value();
+        builder.build_call(
+            module
+                .get_function(INDEX_OUT_OF_BOUNDS)
+                .expect("builtins should be declared in every module"),
+            &[location.into(), index.into(), len.into()],
+            "",
+        );
+        builder.build_unreachable();
+
+        builder.position_at_end(in_bounds_block);
+    }
+
+    unsafe {
+        builder.build_gep(
+            array_type.get_element_type(),
+            array_ptr,
+            &[index],
+            "array_extract",
+        )
     }
 }
@@ -0,0 +1,174 @@
+use crate::parser::expr::Cast;
+use inkwell::{
+    builder::Builder,
+    intrinsics::Intrinsic,
+    module::Module,
+    types::{BasicTypeEnum, IntType},
+    values::{BasicValueEnum, FloatValue, IntValue},
+    FloatPredicate, IntPredicate,
+};
+
+use crate::compiler::{
+    codegen::{
+        get_any_type_from_type_hint,
+        value::{is_unsigned_int, unwrap_unsigned, wrap_unsigned, Value},
+        Codegen,
+    },
+    Compiler,
+};
+
+impl<'a, 'ctx> Codegen<'a, 'ctx> for Cast {
+    fn codegen(
+        self,
+        compiler: &'a crate::compiler::Compiler<'a, 'ctx>,
+        main_function: bool,
+        context: &'ctx inkwell::context::Context,
+        module: &'a inkwell::module::Module<'ctx>,
+        builder: &'a inkwell::builder::Builder<'ctx>,
+        variables: &'a mut Vec<
+            std::collections::HashMap<
+                String,
+                (
+                    inkwell::values::BasicValueEnum<'ctx>,
+                    inkwell::values::PointerValue<'ctx>,
+                ),
+            >,
+        >,
+    ) -> crate::compiler::codegen::Value<'ctx> {
+        let value =
+            self.value
+                .codegen(compiler, main_function, context, module, builder, variables);
+        let target = get_any_type_from_type_hint(compiler, self.r#type.clone());
+
+        cast(compiler, module, builder, value, target).unwrap_or_else(|| {
+            unreachable!("{}: Cannot cast {:#?} to {}", self.span, value, self.r#type)
+        })
+    }
+}
+
+/// Converts a number, char or bool to another of those types, or returns `None` for any other
+/// value or type.
+///
+/// Integers are truncated or extended to fit, so casting wraps around like arithmetic does. Floats
+/// are rounded towards zero when they become integers, and saturate at the bounds of the integer
+/// type. Anything becomes `true` as a bool unless it is zero.
+pub(crate) fn cast<'ctx>(
+    compiler: &Compiler<'_, 'ctx>,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    value: Value<'ctx>,
+    target: BasicTypeEnum<'ctx>,
+) -> Option<Value<'ctx>> {
+    let context = compiler.context;
+    if value.get_type() == Some(target) {
+        return Some(value);
+    }
+
+    let target_unsigned =
+        matches!(target, BasicTypeEnum::StructType(struct_type) if is_unsigned_int(struct_type));
+    let target_int = match target {
+        BasicTypeEnum::IntType(int_type) => Some(int_type),
+        _ if target_unsigned => Some(context.i32_type()),
+        _ => None,
+    };
+
+    let converted: BasicValueEnum = match value {
+        Value::Float(float_value) | Value::Double(float_value) => match target {
+            BasicTypeEnum::FloatType(float_type) => builder
+                .build_float_cast(float_value, float_type, "cast")
+                .into(),
+            _ => {
+                let int_type = target_int?;
+                if int_type.get_bit_width() == 1 {
+                    let zero = float_value.get_type().const_zero();
+                    builder
+                        .build_float_compare(FloatPredicate::UNE, float_value, zero, "cast")
+                        .into()
+                } else {
+                    // Chars are bytes, so they are unsigned too.
+                    let signed = !target_unsigned && int_type.get_bit_width() != 8;
+                    float_to_int(module, builder, float_value, int_type, signed).into()
+                }
+            }
+        },
+        Value::SignedInt(_)
+        | Value::Long(_)
+        | Value::UnsignedInt(_)
+        | Value::Char(_)
+        | Value::Bool(_) => {
+            let (int_value, signed) = match value {
+                Value::SignedInt(int_value) | Value::Long(int_value) => (int_value, true),
+                Value::UnsignedInt(unsigned_value) => {
+                    (unwrap_unsigned(builder, unsigned_value), false)
+                }
+                Value::Char(int_value) | Value::Bool(int_value) => (int_value, false),
+                _ => unreachable!("only integers are matched"),
+            };
+
+            match target {
+                BasicTypeEnum::FloatType(float_type) if signed => builder
+                    .build_signed_int_to_float(int_value, float_type, "cast")
+                    .into(),
+                BasicTypeEnum::FloatType(float_type) => builder
+                    .build_unsigned_int_to_float(int_value, float_type, "cast")
+                    .into(),
+                _ => int_to_int(builder, int_value, target_int?, signed).into(),
+            }
+        }
+        _ => return None,
+    };
+
+    Some(if target_unsigned {
+        Value::UnsignedInt(wrap_unsigned(context, builder, converted.into_int_value()))
+    } else {
+        converted.into()
+    })
+}
+
+fn int_to_int<'ctx>(
+    builder: &Builder<'ctx>,
+    value: IntValue<'ctx>,
+    int_type: IntType<'ctx>,
+    signed: bool,
+) -> IntValue<'ctx> {
+    let (from, to) = (value.get_type().get_bit_width(), int_type.get_bit_width());
+
+    if to == 1 {
+        let zero = value.get_type().const_zero();
+        builder.build_int_compare(IntPredicate::NE, value, zero, "cast")
+    } else if to <= from {
+        builder.build_int_truncate_or_bit_cast(value, int_type, "cast")
+    } else if signed {
+        builder.build_int_s_extend(value, int_type, "cast")
+    } else {
+        builder.build_int_z_extend(value, int_type, "cast")
+    }
+}
+
+/// Converts a float to an integer with `llvm.fptosi.sat` or `llvm.fptoui.sat`, which saturate
+/// rather than giving an undefined value when the float doesn't fit.
+fn float_to_int<'ctx>(
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    value: FloatValue<'ctx>,
+    int_type: IntType<'ctx>,
+    signed: bool,
+) -> IntValue<'ctx> {
+    let intrinsic = if signed {
+        "llvm.fptosi.sat"
+    } else {
+        "llvm.fptoui.sat"
+    };
+    let function = Intrinsic::find(intrinsic)
+        .and_then(|found| {
+            found.get_declaration(module, &[int_type.into(), value.get_type().into()])
+        })
+        .unwrap_or_else(|| panic!("{} should be an LLVM intrinsic", intrinsic));
+
+    builder
+        .build_call(function, &[value.into()], "cast")
+        .try_as_basic_value()
+        .left()
+        .expect("conversion intrinsics should return a value")
+        .into_int_value()
+}
@@ -1,8 +1,11 @@
-use either::Either;
-use inkwell::values::BasicMetadataValueEnum;
 use crate::parser::expr::FunctionCall;
+use either::Either;
+use inkwell::{types::BasicTypeEnum, values::BasicMetadataValueEnum};
 
-use crate::compiler::codegen::{value::Value, Codegen};
+use crate::compiler::codegen::{
+    value::{is_unsigned_int, unwrap_unsigned, Value},
+    Codegen,
+};
 
 impl<'a, 'ctx> Codegen<'a, 'ctx> for FunctionCall {
     fn codegen(
@@ -27,45 +30,40 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for FunctionCall {
             let value =
                 parameter.codegen(compiler, main_function, context, module, builder, variables);
             args.push(match value {
-                Value::Float(float_value) => BasicMetadataValueEnum::FloatValue(float_value),
-                Value::Char(char_value) => BasicMetadataValueEnum::IntValue(char_value),
-                Value::Bool(bool_value) => BasicMetadataValueEnum::IntValue(bool_value),
-                _ => unreachable!(
-                    "Variable type {:#?} not yet supported in function calls",
-                    value
-                ),
+                Value::Void => unreachable!("{}: Cannot pass void to a function", self.span),
+                value => value,
             });
         }
 
-        let mut function = module.get_function(&self.identifier);
+        let span = self.span;
+        let mut function = compiler.get_function(&self.identifier);
 
         if function.is_none() {
             let typed_identifier = format!(
                 "{}_{}",
                 self.identifier,
                 args.iter()
-                    .map(|a| match a {
-                        BasicMetadataValueEnum::FloatValue(_) => "n",
-                        BasicMetadataValueEnum::IntValue(int_value) => {
-                            if int_value.get_type().get_bit_width() == 1 {
-                                "b"
-                            } else {
-                                "c"
-                            }
-                        }
-                        _ => panic!("Cannot convert {:?} to signature", a),
-                    })
+                    .filter_map(|arg| arg.get_type())
+                    .map(signature)
                     .collect::<String>()
             );
 
             function = module.get_function(&typed_identifier);
         }
 
-        let ret = builder.build_direct_call(
-            function.expect("function should be present"),
-            args.as_slice(),
-            "call",
-        );
+        let function = function.unwrap_or_else(|| panic!("{}: function should be present", span));
+        let args = args
+            .into_iter()
+            .zip(function.get_type().get_param_types())
+            .map(|(arg, parameter)| match arg {
+                // Builtins take unsigned integers as plain `i32`s.
+                Value::UnsignedInt(unsigned_value) if parameter.is_int_type() => {
+                    unwrap_unsigned(builder, unsigned_value).into()
+                }
+                arg => BasicMetadataValueEnum::from(arg.into_basic_value()),
+            })
+            .collect::<Vec<_>>();
+        let ret = builder.build_direct_call(function, args.as_slice(), "call");
 
         match ret.try_as_basic_value() {
             Either::Left(value) => value.into(),
@@ -73,3 +71,31 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for FunctionCall {
         }
     }
 }
+
+/// Returns the suffix used to find the overload of a function taking a value of the given type,
+/// such as `n` for `println_n`. Arrays are written as `a`, their length, then their element type,
+/// so a `number[3]` is `a3n`.
+fn signature(ty: BasicTypeEnum) -> String {
+    match ty {
+        BasicTypeEnum::FloatType(float_type)
+            if float_type == float_type.get_context().f64_type() =>
+        {
+            "d".to_owned()
+        }
+        BasicTypeEnum::FloatType(_) => "n".to_owned(),
+        BasicTypeEnum::IntType(int_type) => match int_type.get_bit_width() {
+            1 => "b".to_owned(),
+            32 => "i".to_owned(),
+            64 => "l".to_owned(),
+            _ => "c".to_owned(),
+        },
+        BasicTypeEnum::StructType(struct_type) if is_unsigned_int(struct_type) => "u".to_owned(),
+        BasicTypeEnum::PointerType(_) => "s".to_owned(),
+        BasicTypeEnum::ArrayType(array_type) => format!(
+            "a{}{}",
+            array_type.len(),
+            signature(array_type.get_element_type())
+        ),
+        _ => panic!("Cannot convert {:?} to signature", ty),
+    }
+}
@@ -1,14 +1,22 @@
 use crate::parser::expr::{
-    Array, ArrayIndex, BinOp, Bool, Char, Expr, Float, FunctionCall, Identifier, UnaryMinus,
+    Array, ArrayIndex, BinOp, BitNot, Bool, Cast, Char, Expr, Float, FunctionCall, Identifier,
+    Integer, Interpolation, Not, Object, Str, UnaryMinus,
 };
 
 use super::{value::Value, Codegen};
 
 mod array;
+mod cast;
 mod function_call;
+mod object;
 mod op;
+mod string;
 mod values;
 
+pub(crate) use array::element_pointer;
+pub(crate) use op::binary_op;
+pub(crate) use string::{free, is_temporary};
+
 impl<'a, 'ctx> Codegen<'a, 'ctx> for Expr {
     fn codegen(
         self,
@@ -28,20 +36,32 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for Expr {
         >,
     ) -> super::value::Value<'ctx> {
         match self {
-            Expr::Float(_) => {
+            Expr::Float(..) => {
                 let value: Float = self.try_into().unwrap();
                 value.codegen(compiler, main_function, context, module, builder, variables)
             }
-            Expr::Bool(_) => {
+            Expr::Integer(..) => {
+                let value: Integer = self.try_into().unwrap();
+                value.codegen(compiler, main_function, context, module, builder, variables)
+            }
+            Expr::Bool(..) => {
                 let value: Bool = self.try_into().unwrap();
                 value.codegen(compiler, main_function, context, module, builder, variables)
             }
-            Expr::Char(_) => {
+            Expr::Char(..) => {
                 let value: Char = self.try_into().unwrap();
                 value.codegen(compiler, main_function, context, module, builder, variables)
             }
-            Expr::TypeNone => Value::Void,
-            Expr::Identifier(_) => {
+            Expr::Str(..) => {
+                let value: Str = self.try_into().unwrap();
+                value.codegen(compiler, main_function, context, module, builder, variables)
+            }
+            Expr::Interpolation(..) => {
+                let value: Interpolation = self.try_into().unwrap();
+                value.codegen(compiler, main_function, context, module, builder, variables)
+            }
+            Expr::TypeNone(_) => Value::Void,
+            Expr::Identifier(..) => {
                 let value: Identifier = self.try_into().unwrap();
                 value.codegen(compiler, main_function, context, module, builder, variables)
             }
@@ -49,18 +69,34 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for Expr {
                 let value: FunctionCall = self.try_into().unwrap();
                 value.codegen(compiler, main_function, context, module, builder, variables)
             }
-            Expr::UnaryMinus(_) => {
+            Expr::UnaryMinus(..) => {
                 let value: UnaryMinus = self.try_into().unwrap();
                 value.codegen(compiler, main_function, context, module, builder, variables)
             }
-            Expr::Array(_) => {
+            Expr::Not(..) => {
+                let value: Not = self.try_into().unwrap();
+                value.codegen(compiler, main_function, context, module, builder, variables)
+            }
+            Expr::BitNot(..) => {
+                let value: BitNot = self.try_into().unwrap();
+                value.codegen(compiler, main_function, context, module, builder, variables)
+            }
+            Expr::Array(..) => {
                 let value: Array = self.try_into().unwrap();
                 value.codegen(compiler, main_function, context, module, builder, variables)
             }
+            Expr::Object(..) => {
+                let value: Object = self.try_into().unwrap();
+                value.codegen(compiler, main_function, context, module, builder, variables)
+            }
             Expr::ArrayIndex { .. } => {
                 let value: ArrayIndex = self.try_into().unwrap();
                 value.codegen(compiler, main_function, context, module, builder, variables)
             }
+            Expr::Cast { .. } => {
+                let value: Cast = self.try_into().unwrap();
+                value.codegen(compiler, main_function, context, module, builder, variables)
+            }
             Expr::BinOp { .. } => {
                 let value: BinOp = self.try_into().unwrap();
                 value.codegen(compiler, main_function, context, module, builder, variables)
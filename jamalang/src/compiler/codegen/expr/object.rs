@@ -0,0 +1,47 @@
+use crate::parser::expr::Object;
+
+use crate::compiler::codegen::{value::Value, Codegen};
+
+impl<'a, 'ctx> Codegen<'a, 'ctx> for Object {
+    fn codegen(
+        self,
+        compiler: &'a crate::compiler::Compiler<'a, 'ctx>,
+        main_function: bool,
+        context: &'ctx inkwell::context::Context,
+        module: &'a inkwell::module::Module<'ctx>,
+        builder: &'a inkwell::builder::Builder<'ctx>,
+        variables: &'a mut Vec<
+            std::collections::HashMap<
+                String,
+                (
+                    inkwell::values::BasicValueEnum<'ctx>,
+                    inkwell::values::PointerValue<'ctx>,
+                ),
+            >,
+        >,
+    ) -> crate::compiler::codegen::Value<'ctx> {
+        // Fields are evaluated in the order they were written, but stored sorted by name.
+        let mut fields = Vec::new();
+        for (name, value) in self.0 {
+            let value = value.codegen(compiler, main_function, context, module, builder, variables);
+            fields.push((name, value.into_basic_value()));
+        }
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let record = compiler.record_type(
+            &fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value.get_type()))
+                .collect::<Vec<_>>(),
+        );
+        let mut value = record.get_undef().into();
+
+        for (index, (name, field)) in fields.into_iter().enumerate() {
+            value = builder
+                .build_insert_value(value, field, index as u32, &format!("insert_{}", name))
+                .expect("field index should be valid");
+        }
+
+        Value::Record(value.into_struct_value())
+    }
+}
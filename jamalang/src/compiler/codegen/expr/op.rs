@@ -1,7 +1,24 @@
-use inkwell::FloatPredicate;
-use crate::parser::expr::{BinOp, Op, UnaryMinus};
+use crate::diagnostic::Span;
+use crate::parser::expr::{BinOp, BitNot, Expr, Not, Op, UnaryMinus};
+use inkwell::{
+    builder::Builder,
+    context::Context,
+    intrinsics::Intrinsic,
+    module::Module,
+    values::{BasicValueEnum, FloatValue, IntValue},
+    FloatPredicate, IntPredicate,
+};
 
-use crate::compiler::codegen::{value::Value, Codegen};
+use crate::compiler::{
+    builtins::{DIVISION_BY_ZERO, INTEGER_POWER, STRING_EQUALS},
+    codegen::{
+        value::{unsigned_int_type, unwrap_unsigned, wrap_unsigned, Value},
+        Codegen,
+    },
+    Compiler,
+};
+
+use super::string::{call_builtin, concat, is_temporary, StringParts};
 
 impl<'a, 'ctx> Codegen<'a, 'ctx> for UnaryMinus {
     fn codegen(
@@ -26,7 +43,81 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for UnaryMinus {
             .codegen(compiler, main_function, context, module, builder, variables);
         match value {
             Value::Float(float_value) => Value::Float(builder.build_float_neg(float_value, "neg")),
-            _ => unreachable!("Cannot perform unary minus on {:#?}", value),
+            Value::Double(float_value) => {
+                Value::Double(builder.build_float_neg(float_value, "neg"))
+            }
+            Value::SignedInt(int_value) => {
+                Value::SignedInt(builder.build_int_neg(int_value, "neg"))
+            }
+            Value::Long(int_value) => Value::Long(builder.build_int_neg(int_value, "neg")),
+            // Only `-0` can be an `UnsignedInt`, as it is the one negative literal that fits in one.
+            Value::UnsignedInt(unsigned_value) => {
+                let int_value =
+                    builder.build_int_neg(unwrap_unsigned(builder, unsigned_value), "neg");
+                Value::UnsignedInt(wrap_unsigned(context, builder, int_value))
+            }
+            _ => unreachable!("{}: Cannot perform unary minus on {:#?}", self.1, value),
+        }
+    }
+}
+
+impl<'a, 'ctx> Codegen<'a, 'ctx> for Not {
+    fn codegen(
+        self,
+        compiler: &'a crate::compiler::Compiler<'a, 'ctx>,
+        main_function: bool,
+        context: &'ctx inkwell::context::Context,
+        module: &'a inkwell::module::Module<'ctx>,
+        builder: &'a inkwell::builder::Builder<'ctx>,
+        variables: &'a mut Vec<
+            std::collections::HashMap<
+                String,
+                (
+                    inkwell::values::BasicValueEnum<'ctx>,
+                    inkwell::values::PointerValue<'ctx>,
+                ),
+            >,
+        >,
+    ) -> crate::compiler::codegen::Value<'ctx> {
+        let value = self
+            .0
+            .codegen(compiler, main_function, context, module, builder, variables);
+        match value {
+            Value::Bool(bool_value) => Value::Bool(builder.build_not(bool_value, "not")),
+            _ => unreachable!("{}: Cannot perform not on {:#?}", self.1, value),
+        }
+    }
+}
+
+impl<'a, 'ctx> Codegen<'a, 'ctx> for BitNot {
+    fn codegen(
+        self,
+        compiler: &'a crate::compiler::Compiler<'a, 'ctx>,
+        main_function: bool,
+        context: &'ctx inkwell::context::Context,
+        module: &'a inkwell::module::Module<'ctx>,
+        builder: &'a inkwell::builder::Builder<'ctx>,
+        variables: &'a mut Vec<
+            std::collections::HashMap<
+                String,
+                (
+                    inkwell::values::BasicValueEnum<'ctx>,
+                    inkwell::values::PointerValue<'ctx>,
+                ),
+            >,
+        >,
+    ) -> crate::compiler::codegen::Value<'ctx> {
+        let value = self
+            .0
+            .codegen(compiler, main_function, context, module, builder, variables);
+        match value {
+            Value::SignedInt(int_value) => Value::SignedInt(builder.build_not(int_value, "not")),
+            Value::Long(int_value) => Value::Long(builder.build_not(int_value, "not")),
+            Value::UnsignedInt(unsigned_value) => {
+                let int_value = builder.build_not(unwrap_unsigned(builder, unsigned_value), "not");
+                Value::UnsignedInt(wrap_unsigned(context, builder, int_value))
+            }
+            _ => unreachable!("{}: Cannot perform bitwise not on {:#?}", self.1, value),
         }
     }
 }
@@ -49,95 +140,559 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for BinOp {
             >,
         >,
     ) -> crate::compiler::codegen::Value<'ctx> {
+        if self.op == Op::Add {
+            // `a + b + c` is `(a + b) + c`, so the rest of a chain of `+`s is down its left side.
+            let mut operands = vec![(*self.rhs, self.span)];
+            let mut first = *self.lhs;
+            while let Expr::BinOp {
+                lhs,
+                op: Op::Add,
+                rhs,
+                span,
+            } = first
+            {
+                operands.push((*rhs, span));
+                first = *lhs;
+            }
+            operands.reverse();
+
+            let temporary = is_temporary(&first);
+            let mut value =
+                first.codegen(compiler, main_function, context, module, builder, variables);
+
+            // The strings are all joined at once, rather than making a new string for every `+`.
+            if let Value::String(string) = value {
+                let mut parts = StringParts::default();
+                parts.push_string(string, temporary);
+
+                for (operand, span) in operands {
+                    let temporary = is_temporary(&operand);
+                    let Value::String(string) = operand.codegen(
+                        compiler,
+                        main_function,
+                        context,
+                        module,
+                        builder,
+                        variables,
+                    ) else {
+                        unreachable!("{}: Cannot add a string to something else", span)
+                    };
+                    parts.push_string(string, temporary);
+                }
+
+                return Value::String(parts.build(compiler, module, builder));
+            }
+
+            for (operand, span) in operands {
+                let rhs =
+                    operand.codegen(compiler, main_function, context, module, builder, variables);
+                value = binary_op(compiler, module, builder, Op::Add, value, rhs, &span);
+            }
+
+            return value;
+        }
+
         let lhs = self
             .lhs
             .codegen(compiler, main_function, context, module, builder, variables);
+
+        if let Op::And | Op::Or = self.op {
+            // The right side is only evaluated when the left side doesn't decide the result, so
+            // it gets its own block, and a phi picks the result from whichever way we came.
+            let Value::Bool(lhs) = lhs else {
+                unreachable!("{}: Cannot perform {:?} on {:#?}", self.span, self.op, lhs)
+            };
+            let lhs_block = builder
+                .get_insert_block()
+                .expect("insert block should be present");
+            let function = lhs_block.get_parent().expect("function should be present");
+            let rhs_block = context.append_basic_block(function, "rhs");
+            let merge_block = context.append_basic_block(function, "merge");
+
+            let short_circuit = if self.op == Op::And {
+                builder.build_conditional_branch(lhs, rhs_block, merge_block);
+                context.bool_type().const_zero()
+            } else {
+                builder.build_conditional_branch(lhs, merge_block, rhs_block);
+                context.bool_type().const_all_ones()
+            };
+
+            builder.position_at_end(rhs_block);
+            let rhs =
+                self.rhs
+                    .codegen(compiler, main_function, context, module, builder, variables);
+            let Value::Bool(rhs) = rhs else {
+                unreachable!("{}: Cannot perform {:?} on {:#?}", self.span, self.op, rhs)
+            };
+            // The right side may have added blocks of its own.
+            let rhs_block = builder
+                .get_insert_block()
+                .expect("insert block should be present");
+            builder.build_unconditional_branch(merge_block);
+
+            builder.position_at_end(merge_block);
+            let phi = builder.build_phi(context.bool_type(), "logical");
+            phi.add_incoming(&[(&short_circuit, lhs_block), (&rhs, rhs_block)]);
+
+            return Value::Bool(phi.as_basic_value().into_int_value());
+        }
+
         let rhs = self
             .rhs
             .codegen(compiler, main_function, context, module, builder, variables);
 
-        match self.op {
-            Op::Add => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    Value::Float(builder.build_float_add(lhs, rhs, "add"))
-                }
-                _ => todo!("Cannot yet add {:?} and {:?}", lhs, rhs),
-            },
-            Op::Subtract => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    Value::Float(builder.build_float_sub(lhs, rhs, "sub"))
-                }
-                _ => todo!("Cannot yet subtract {:?} and {:?}", lhs, rhs),
-            },
-            Op::Multiply => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    Value::Float(builder.build_float_mul(lhs, rhs, "mul"))
-                }
-                _ => todo!("Cannot yet multiply {:?} and {:?}", lhs, rhs),
-            },
-            Op::Divide => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    Value::Float(builder.build_float_div(lhs, rhs, "div"))
-                }
-                _ => todo!("Cannot yet divide {:?} and {:?}", lhs, rhs),
-            },
-            Op::Modulo => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    Value::Float(builder.build_float_rem(lhs, rhs, "rem"))
-                }
-                _ => todo!("Cannot yet modulo {:?} and {:?}", lhs, rhs),
-            },
-            Op::Power => todo!("Power operator not implemented"),
-            Op::Range => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    let start =  lhs.get_constant().expect("range expressions should currently be constant").0 as u32;
-                    let end =  rhs.get_constant().expect("range expressions should currently be constant").0 as u32;
-                    Value::Array(context.f32_type().const_array((start..end).map(|e| context.f32_type().const_float(e as f64)).collect::<Vec<_>>().as_slice()))
-                }
-                _ => todo!("Cannot yet range {:?} and {:?}", lhs, rhs),
-            },
-            Op::Lt => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    let cmp = builder.build_float_compare(FloatPredicate::ULT, lhs, rhs, "cmp");
-                    Value::Bool(cmp)
-                }
-                _ => todo!("Cannot yet LT {:?} and {:?}", lhs, rhs),
-            },
-            Op::Gt => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    let cmp = builder.build_float_compare(FloatPredicate::UGT, lhs, rhs, "cmp");
-                    Value::Bool(cmp)
-                }
-                _ => todo!("Cannot yet GT {:?} and {:?}", lhs, rhs),
-            },
-            Op::Lte => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    let cmp = builder.build_float_compare(FloatPredicate::ULE, lhs, rhs, "cmp");
-                    Value::Bool(cmp)
-                }
-                _ => todo!("Cannot yet LTE {:?} and {:?}", lhs, rhs),
-            },
-            Op::Gte => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    let cmp = builder.build_float_compare(FloatPredicate::UGE, lhs, rhs, "cmp");
-                    Value::Bool(cmp)
-                }
-                _ => todo!("Cannot yet GTE {:?} and {:?}", lhs, rhs),
-            },
-            Op::Eq => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    let cmp = builder.build_float_compare(FloatPredicate::UEQ, lhs, rhs, "cmp");
-                    Value::Bool(cmp)
-                }
-                _ => todo!("Cannot yet EQ {:?} and {:?}", lhs, rhs),
-            },
-            Op::NotEq => match (lhs, rhs) {
-                (Value::Float(lhs), Value::Float(rhs)) => {
-                    let cmp = builder.build_float_compare(FloatPredicate::UNE, lhs, rhs, "cmp");
-                    Value::Bool(cmp)
+        binary_op(compiler, module, builder, self.op, lhs, rhs, &self.span)
+    }
+}
+
+/// Applies a binary operator to two values that have already been generated, which analysis has
+/// made sure have the same type.
+pub(crate) fn binary_op<'ctx>(
+    compiler: &Compiler<'_, 'ctx>,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    op: Op,
+    lhs: Value<'ctx>,
+    rhs: Value<'ctx>,
+    span: &Span,
+) -> Value<'ctx> {
+    let context = compiler.context;
+
+    if let Some(value) = integer_op(compiler, module, builder, op, lhs, rhs, span) {
+        return value;
+    }
+    if let Some(value) = float_op(context, module, builder, op, lhs, rhs) {
+        return value;
+    }
+
+    let compare =
+        |predicate, lhs, rhs| Value::Bool(builder.build_int_compare(predicate, lhs, rhs, "cmp"));
+
+    match (op, lhs, rhs) {
+        (Op::Add, Value::String(lhs), Value::String(rhs)) => {
+            Value::String(concat(compiler, module, builder, &[lhs, rhs]))
+        }
+        (Op::Range, Value::Float(lhs), Value::Float(rhs))
+        | (Op::Range, Value::Double(lhs), Value::Double(rhs)) => {
+            let start = lhs
+                .get_constant()
+                .expect("range expressions should currently be constant")
+                .0 as u32;
+            let end = rhs
+                .get_constant()
+                .expect("range expressions should currently be constant")
+                .0 as u32;
+            let float_type = lhs.get_type();
+            Value::Array(
+                float_type.const_array(
+                    (start..end)
+                        .map(|e| float_type.const_float(e as f64))
+                        .collect::<Vec<_>>()
+                        .as_slice(),
+                ),
+            )
+        }
+        (Op::Range, Value::SignedInt(lhs), Value::SignedInt(rhs))
+        | (Op::Range, Value::Long(lhs), Value::Long(rhs)) => {
+            let start = lhs
+                .get_sign_extended_constant()
+                .expect("range expressions should currently be constant");
+            let end = rhs
+                .get_sign_extended_constant()
+                .expect("range expressions should currently be constant");
+            let int_type = lhs.get_type();
+            Value::Array(
+                int_type.const_array(
+                    (start..end)
+                        .map(|e| int_type.const_int(e as u64, true))
+                        .collect::<Vec<_>>()
+                        .as_slice(),
+                ),
+            )
+        }
+        (Op::Range, Value::UnsignedInt(lhs), Value::UnsignedInt(rhs)) => {
+            // The builder folds the `i32` inside a constant unsigned integer into a constant.
+            let bound = |value| {
+                unwrap_unsigned(builder, value)
+                    .get_zero_extended_constant()
+                    .expect("range expressions should currently be constant")
+            };
+            let (start, end) = (bound(lhs), bound(rhs));
+            let int_type = context.i32_type();
+            let unsigned_type = unsigned_int_type(context);
+            Value::Array(
+                unsigned_type.const_array(
+                    (start..end)
+                        .map(|e| {
+                            unsigned_type.const_named_struct(&[int_type.const_int(e, false).into()])
+                        })
+                        .collect::<Vec<_>>()
+                        .as_slice(),
+                ),
+            )
+        }
+        (Op::Lt, Value::Char(lhs), Value::Char(rhs)) => compare(IntPredicate::ULT, lhs, rhs),
+        (Op::Gt, Value::Char(lhs), Value::Char(rhs)) => compare(IntPredicate::UGT, lhs, rhs),
+        (Op::Lte, Value::Char(lhs), Value::Char(rhs)) => compare(IntPredicate::ULE, lhs, rhs),
+        (Op::Gte, Value::Char(lhs), Value::Char(rhs)) => compare(IntPredicate::UGE, lhs, rhs),
+        (Op::Eq | Op::NotEq, lhs, rhs) if lhs.get_type().is_some() && rhs.get_type().is_some() => {
+            let eq = equals(
+                module,
+                builder,
+                lhs.into_basic_value(),
+                rhs.into_basic_value(),
+            );
+            if op == Op::Eq {
+                Value::Bool(eq)
+            } else {
+                Value::Bool(builder.build_not(eq, "not"))
+            }
+        }
+        (Op::And, Value::Bool(lhs), Value::Bool(rhs)) => {
+            Value::Bool(builder.build_and(lhs, rhs, "and"))
+        }
+        (Op::Or, Value::Bool(lhs), Value::Bool(rhs)) => {
+            Value::Bool(builder.build_or(lhs, rhs, "or"))
+        }
+        (op, lhs, rhs) => unreachable!(
+            "{}: Cannot perform {:?} on {:#?} and {:#?}",
+            span, op, lhs, rhs
+        ),
+    }
+}
+
+/// Applies an arithmetic or comparison operator to two floats of the same type, or returns `None`
+/// if the operands aren't floats or the operator isn't one of those.
+fn float_op<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    op: Op,
+    lhs: Value<'ctx>,
+    rhs: Value<'ctx>,
+) -> Option<Value<'ctx>> {
+    let (lhs, rhs) = match (lhs, rhs) {
+        (Value::Float(lhs), Value::Float(rhs)) | (Value::Double(lhs), Value::Double(rhs)) => {
+            (lhs, rhs)
+        }
+        _ => return None,
+    };
+
+    let compare = |predicate| {
+        Some(Value::Bool(
+            builder.build_float_compare(predicate, lhs, rhs, "cmp"),
+        ))
+    };
+
+    let result = match op {
+        Op::Add => builder.build_float_add(lhs, rhs, "add"),
+        Op::Subtract => builder.build_float_sub(lhs, rhs, "sub"),
+        Op::Multiply => builder.build_float_mul(lhs, rhs, "mul"),
+        Op::Divide => builder.build_float_div(lhs, rhs, "div"),
+        Op::Modulo => builder.build_float_rem(lhs, rhs, "rem"),
+        Op::Power => power(context, module, builder, lhs, rhs),
+        Op::Lt => return compare(FloatPredicate::ULT),
+        Op::Gt => return compare(FloatPredicate::UGT),
+        Op::Lte => return compare(FloatPredicate::ULE),
+        Op::Gte => return compare(FloatPredicate::UGE),
+        Op::Eq => return compare(FloatPredicate::UEQ),
+        Op::NotEq => return compare(FloatPredicate::UNE),
+        Op::Range
+        | Op::And
+        | Op::Or
+        | Op::BitAnd
+        | Op::BitOr
+        | Op::BitXor
+        | Op::ShiftLeft
+        | Op::ShiftRight => return None,
+    };
+
+    Some(BasicValueEnum::FloatValue(result).into())
+}
+
+/// Applies an arithmetic, bitwise or comparison operator to two integers of the same type, or
+/// returns `None` if the operands aren't integers or the operator isn't one of those. Division,
+/// remainders, ordering and shifting right depend on whether the integers are signed.
+fn integer_op<'ctx>(
+    compiler: &Compiler<'_, 'ctx>,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    op: Op,
+    lhs: Value<'ctx>,
+    rhs: Value<'ctx>,
+    span: &Span,
+) -> Option<Value<'ctx>> {
+    let context = compiler.context;
+    let (lhs, rhs, signed) = match (lhs, rhs) {
+        (Value::SignedInt(lhs), Value::SignedInt(rhs)) | (Value::Long(lhs), Value::Long(rhs)) => {
+            (lhs, rhs, true)
+        }
+        (Value::UnsignedInt(lhs), Value::UnsignedInt(rhs)) => (
+            unwrap_unsigned(builder, lhs),
+            unwrap_unsigned(builder, rhs),
+            false,
+        ),
+        _ => return None,
+    };
+
+    let compare = |signed_predicate, unsigned_predicate| {
+        let predicate = if signed {
+            signed_predicate
+        } else {
+            unsigned_predicate
+        };
+        Some(Value::Bool(
+            builder.build_int_compare(predicate, lhs, rhs, "cmp"),
+        ))
+    };
+
+    let result = match op {
+        Op::Add => builder.build_int_add(lhs, rhs, "add"),
+        Op::Subtract => builder.build_int_sub(lhs, rhs, "sub"),
+        Op::Multiply => builder.build_int_mul(lhs, rhs, "mul"),
+        Op::Divide | Op::Modulo => divide(module, builder, op, lhs, rhs, signed, span),
+        Op::Power => integer_power(module, builder, lhs, rhs, signed, span),
+        Op::Lt => return compare(IntPredicate::SLT, IntPredicate::ULT),
+        Op::Gt => return compare(IntPredicate::SGT, IntPredicate::UGT),
+        Op::Lte => return compare(IntPredicate::SLE, IntPredicate::ULE),
+        Op::Gte => return compare(IntPredicate::SGE, IntPredicate::UGE),
+        Op::Eq => return compare(IntPredicate::EQ, IntPredicate::EQ),
+        Op::NotEq => return compare(IntPredicate::NE, IntPredicate::NE),
+        Op::BitAnd => builder.build_and(lhs, rhs, "and"),
+        Op::BitOr => builder.build_or(lhs, rhs, "or"),
+        Op::BitXor => builder.build_xor(lhs, rhs, "xor"),
+        Op::ShiftLeft => builder.build_left_shift(lhs, shift_amount(builder, rhs), "shl"),
+        // Signed integers keep their sign as they are shifted right, unsigned ones fill with 0s.
+        Op::ShiftRight => builder.build_right_shift(lhs, shift_amount(builder, rhs), signed, "shr"),
+        Op::Range | Op::And | Op::Or => return None,
+    };
+
+    Some(if signed {
+        BasicValueEnum::IntValue(result).into()
+    } else {
+        Value::UnsignedInt(wrap_unsigned(context, builder, result))
+    })
+}
+
+/// Divides two integers or finds the remainder of dividing them. Dividing by zero has no defined
+/// result in LLVM, so the program stops with a runtime error if the divisor is zero. The one
+/// signed division that overflows, the smallest value divided by -1, wraps around like the rest
+/// of arithmetic does, giving the smallest value with a remainder of 0.
+fn divide<'ctx>(
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    op: Op,
+    lhs: IntValue<'ctx>,
+    rhs: IntValue<'ctx>,
+    signed: bool,
+    span: &Span,
+) -> IntValue<'ctx> {
+    let int_type = rhs.get_type();
+
+    // Dividing by a constant other than zero needs no check.
+    if rhs.get_zero_extended_constant().unwrap_or(0) == 0 {
+        let is_zero =
+            builder.build_int_compare(IntPredicate::EQ, rhs, int_type.const_zero(), "is_zero");
+        check_division_by_zero(module, builder, is_zero, span);
+    }
+
+    if !signed {
+        return match op {
+            Op::Divide => builder.build_int_unsigned_div(lhs, rhs, "div"),
+            _ => builder.build_int_unsigned_rem(lhs, rhs, "rem"),
+        };
+    }
+
+    // Dividing by 1 instead of -1 can't overflow, and negating the quotient afterwards wraps around.
+    // The remainder of dividing by either is 0.
+    let minus_one = int_type.const_all_ones();
+    let is_minus_one = builder.build_int_compare(IntPredicate::EQ, rhs, minus_one, "is_minus_one");
+    let divisor = builder
+        .build_select(is_minus_one, int_type.const_int(1, false), rhs, "divisor")
+        .into_int_value();
+
+    match op {
+        Op::Divide => {
+            let quotient = builder.build_int_signed_div(lhs, divisor, "div");
+            let negated = builder.build_int_neg(quotient, "neg");
+            builder
+                .build_select(is_minus_one, negated, quotient, "div")
+                .into_int_value()
+        }
+        _ => builder.build_int_signed_rem(lhs, divisor, "rem"),
+    }
+}
+
+/// Stops the program with a runtime error at `span` if `is_zero` is true, leaving the builder
+/// where the program carries on otherwise.
+fn check_division_by_zero<'ctx>(
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    is_zero: IntValue<'ctx>,
+    span: &Span,
+) {
+    let context = module.get_context();
+    let function = builder
+        .get_insert_block()
+        .and_then(|block| block.get_parent())
+        .expect("function should be present");
+    let zero_block = context.append_basic_block(function, "division_by_zero");
+    let divide_block = context.append_basic_block(function, "divide");
+    builder.build_conditional_branch(is_zero, zero_block, divide_block);
+
+    builder.position_at_end(zero_block);
+    let location = builder
+        .build_global_string_ptr(&span.to_string(), "location")
+        .as_pointer_value();
+    builder.build_call(
+        module
+            .get_function(DIVISION_BY_ZERO)
+            .expect("builtins should be declared in every module"),
+        &[location.into()],
+        "",
+    );
+    builder.build_unreachable();
+
+    builder.position_at_end(divide_block);
+}
+
+/// Wraps a shift amount around to less than the width of the integer being shifted, as shifting by
+/// the whole width or more has no defined result in LLVM. `x << 33` is the same as `x << 1` for a
+/// 32-bit `x`.
+fn shift_amount<'ctx>(builder: &Builder<'ctx>, amount: IntValue<'ctx>) -> IntValue<'ctx> {
+    let int_type = amount.get_type();
+    let mask = int_type.const_int(int_type.get_bit_width() as u64 - 1, false);
+    builder.build_and(amount, mask, "amount")
+}
+
+/// Raises an integer to a power with the `integer_power` builtin, which works on 64-bit integers.
+/// A negative power divides 1 by the base, so raising 0 to one is a division by zero.
+fn integer_power<'ctx>(
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    base: IntValue<'ctx>,
+    exponent: IntValue<'ctx>,
+    signed: bool,
+    span: &Span,
+) -> IntValue<'ctx> {
+    let int_type = base.get_type();
+
+    // Unsigned exponents and constant ones that aren't negative need no check.
+    if signed && exponent.get_sign_extended_constant().unwrap_or(-1) < 0 {
+        let zero = int_type.const_zero();
+        let is_zero = builder.build_int_compare(IntPredicate::EQ, base, zero, "is_zero");
+        let negative = builder.build_int_compare(IntPredicate::SLT, exponent, zero, "negative");
+        let is_zero = builder.build_and(is_zero, negative, "zero_to_negative");
+        check_division_by_zero(module, builder, is_zero, span);
+    }
+
+    let long_type = module.get_context().i64_type();
+    let extend = |value| {
+        if signed {
+            builder.build_int_s_extend_or_bit_cast(value, long_type, "extend")
+        } else {
+            builder.build_int_z_extend_or_bit_cast(value, long_type, "extend")
+        }
+    };
+
+    let result = call_builtin(
+        module,
+        builder,
+        INTEGER_POWER,
+        &[extend(base).into(), extend(exponent).into()],
+    )
+    .into_int_value();
+
+    // Wrapping in 64 bits and then truncating wraps the same way as the narrower type would.
+    builder.build_int_truncate_or_bit_cast(result, int_type, "pow")
+}
+
+/// Compares two values of the same type. Arrays and records are equal when each of their elements
+/// or fields are.
+fn equals<'ctx>(
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    lhs: BasicValueEnum<'ctx>,
+    rhs: BasicValueEnum<'ctx>,
+) -> IntValue<'ctx> {
+    let aggregate_equals = |len: u32| {
+        let mut result = module.get_context().bool_type().const_all_ones();
+
+        for index in 0..len {
+            let extract = |value: BasicValueEnum<'ctx>| {
+                match value {
+                    BasicValueEnum::ArrayValue(array) => {
+                        builder.build_extract_value(array, index, "element")
+                    }
+                    BasicValueEnum::StructValue(record) => {
+                        builder.build_extract_value(record, index, "field")
+                    }
+                    _ => unreachable!("only arrays and records have elements"),
                 }
-                _ => todo!("Cannot yet NEQ {:?} and {:?}", lhs, rhs),
-            },
+                .expect("index should be within the value")
+            };
+            let eq = equals(module, builder, extract(lhs), extract(rhs));
+            result = builder.build_and(result, eq, "and");
+        }
+
+        result
+    };
+
+    match (lhs, rhs) {
+        (BasicValueEnum::FloatValue(lhs), BasicValueEnum::FloatValue(rhs)) => {
+            builder.build_float_compare(FloatPredicate::UEQ, lhs, rhs, "eq")
+        }
+        (BasicValueEnum::IntValue(lhs), BasicValueEnum::IntValue(rhs)) => {
+            builder.build_int_compare(IntPredicate::EQ, lhs, rhs, "eq")
         }
+        (BasicValueEnum::PointerValue(lhs), BasicValueEnum::PointerValue(rhs)) => {
+            call_builtin(module, builder, STRING_EQUALS, &[lhs.into(), rhs.into()]).into_int_value()
+        }
+        (BasicValueEnum::ArrayValue(array), BasicValueEnum::ArrayValue(_)) => {
+            aggregate_equals(array.get_type().len())
+        }
+        (BasicValueEnum::StructValue(record), BasicValueEnum::StructValue(_)) => {
+            aggregate_equals(record.get_type().count_fields())
+        }
+        _ => unreachable!("only values of the same type are compared"),
     }
 }
+
+/// Raises a number to a power. Whole constant exponents use `llvm.powi`, which LLVM can turn into
+/// multiplications, and any other exponent uses `llvm.pow`.
+fn power<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    base: FloatValue<'ctx>,
+    exponent: FloatValue<'ctx>,
+) -> FloatValue<'ctx> {
+    let float_type = base.get_type();
+    let whole_exponent = exponent
+        .get_constant()
+        .map(|(value, _)| value)
+        .filter(|value| value.fract() == 0.0 && value.abs() <= i32::MAX as f64);
+
+    let (intrinsic, types, exponent) = match whole_exponent {
+        Some(value) => {
+            let int_type = context.i32_type();
+            (
+                "llvm.powi",
+                vec![float_type.into(), int_type.into()],
+                int_type.const_int(value as i64 as u64, true).into(),
+            )
+        }
+        None => ("llvm.pow", vec![float_type.into()], exponent.into()),
+    };
+
+    let function = Intrinsic::find(intrinsic)
+        .and_then(|intrinsic| intrinsic.get_declaration(module, &types))
+        .unwrap_or_else(|| panic!("{} should be an LLVM intrinsic", intrinsic));
+
+    builder
+        .build_call(function, &[base.into(), exponent], "pow")
+        .try_as_basic_value()
+        .left()
+        .expect("power intrinsics should return a value")
+        .into_float_value()
+}
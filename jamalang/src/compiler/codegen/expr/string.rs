@@ -0,0 +1,258 @@
+use crate::diagnostic::Span;
+use crate::parser::expr::{Expr, Interpolation, Op};
+use inkwell::{
+    builder::Builder,
+    module::Module,
+    values::{BasicMetadataValueEnum, BasicValueEnum, PointerValue},
+    AddressSpace,
+};
+
+use crate::compiler::{
+    builtins::{
+        BOOL_TO_STRING, CHAR_TO_STRING, DOUBLE_TO_STRING, NUMBER_TO_STRING, SIGNED_TO_STRING,
+        STRING_CONCAT, STRING_FREE, UNSIGNED_TO_STRING,
+    },
+    codegen::{
+        entry_alloca,
+        value::{unwrap_unsigned, Value},
+        Codegen,
+    },
+    Compiler,
+};
+
+impl<'a, 'ctx> Codegen<'a, 'ctx> for Interpolation {
+    fn codegen(
+        self,
+        compiler: &'a crate::compiler::Compiler<'a, 'ctx>,
+        main_function: bool,
+        context: &'ctx inkwell::context::Context,
+        module: &'a inkwell::module::Module<'ctx>,
+        builder: &'a inkwell::builder::Builder<'ctx>,
+        variables: &'a mut Vec<
+            std::collections::HashMap<
+                String,
+                (
+                    inkwell::values::BasicValueEnum<'ctx>,
+                    inkwell::values::PointerValue<'ctx>,
+                ),
+            >,
+        >,
+    ) -> crate::compiler::codegen::Value<'ctx> {
+        // Every part is generated first, and then they are all joined at once.
+        let mut parts = StringParts::default();
+
+        for part in self.0 {
+            let span = part.span().clone();
+            let temporary = is_temporary(&part);
+
+            match part.codegen(compiler, main_function, context, module, builder, variables) {
+                Value::String(string) => parts.push_string(string, temporary),
+                value => parts.push_value(compiler, module, builder, value, &span),
+            }
+        }
+
+        Value::String(parts.build(compiler, module, builder))
+    }
+}
+
+/// The pieces of a string being built, which are joined all at once when it is finished so that
+/// it is allocated once rather than once per piece.
+#[derive(Default)]
+pub(crate) struct StringParts<'ctx> {
+    parts: Vec<PointerValue<'ctx>>,
+    /// The parts that were created only to be joined, such as numbers converted to strings. They
+    /// are freed once the string is built.
+    temporaries: Vec<PointerValue<'ctx>>,
+}
+
+impl<'ctx> StringParts<'ctx> {
+    pub(crate) fn push_str(&mut self, builder: &Builder<'ctx>, text: &str) {
+        let string = builder.build_global_string_ptr(text, "string");
+        self.parts.push(string.as_pointer_value());
+    }
+
+    /// Adds a string, which is freed once the string is built if nothing else can refer to it.
+    pub(crate) fn push_string(&mut self, string: PointerValue<'ctx>, temporary: bool) {
+        self.parts.push(string);
+
+        if temporary {
+            self.temporaries.push(string);
+        }
+    }
+
+    /// Adds a value, formatted the same way as `println` formats it.
+    pub(crate) fn push_value(
+        &mut self,
+        compiler: &Compiler<'_, 'ctx>,
+        module: &Module<'ctx>,
+        builder: &Builder<'ctx>,
+        value: Value<'ctx>,
+        span: &Span,
+    ) {
+        let converted = match value {
+            Value::String(string_value) => return self.push_string(string_value, false),
+            Value::Float(float_value) => {
+                call_builtin(module, builder, NUMBER_TO_STRING, &[float_value.into()])
+            }
+            Value::Double(double_value) => {
+                call_builtin(module, builder, DOUBLE_TO_STRING, &[double_value.into()])
+            }
+            Value::UnsignedInt(unsigned_value) => {
+                let int_value = builder.build_int_z_extend(
+                    unwrap_unsigned(builder, unsigned_value),
+                    module.get_context().i64_type(),
+                    "extend",
+                );
+                call_builtin(module, builder, UNSIGNED_TO_STRING, &[int_value.into()])
+            }
+            Value::SignedInt(int_value) | Value::Long(int_value) => {
+                let int_value = builder.build_int_s_extend_or_bit_cast(
+                    int_value,
+                    module.get_context().i64_type(),
+                    "extend",
+                );
+                call_builtin(module, builder, SIGNED_TO_STRING, &[int_value.into()])
+            }
+            Value::Char(char_value) => {
+                call_builtin(module, builder, CHAR_TO_STRING, &[char_value.into()])
+            }
+            Value::Bool(bool_value) => {
+                call_builtin(module, builder, BOOL_TO_STRING, &[bool_value.into()])
+            }
+            Value::Array(array_value) => {
+                self.push_str(builder, "[");
+
+                for index in 0..array_value.get_type().len() {
+                    if index > 0 {
+                        self.push_str(builder, ", ");
+                    }
+
+                    let element = builder
+                        .build_extract_value(array_value, index, "element")
+                        .expect("index should be within the array");
+                    self.push_value(compiler, module, builder, element.into(), span);
+                }
+
+                return self.push_str(builder, "]");
+            }
+            Value::Record(record_value) => {
+                let names = compiler
+                    .record_fields(record_value.get_type())
+                    .expect("records should have field names");
+                self.push_str(builder, "{");
+
+                for (index, name) in names.iter().enumerate() {
+                    let separator = if index > 0 { ", " } else { "" };
+                    self.push_str(builder, &format!("{}{}: ", separator, name));
+
+                    let field = builder
+                        .build_extract_value(record_value, index as u32, "field")
+                        .expect("field index should be valid");
+                    self.push_value(compiler, module, builder, field.into(), span);
+                }
+
+                return self.push_str(builder, "}");
+            }
+            Value::Void => panic!("{}: Cannot convert void to a string", span),
+        };
+
+        self.push_string(converted.into_pointer_value(), true);
+    }
+
+    /// Joins the parts into a new string and frees the temporary ones.
+    pub(crate) fn build(
+        self,
+        compiler: &Compiler<'_, 'ctx>,
+        module: &Module<'ctx>,
+        builder: &Builder<'ctx>,
+    ) -> PointerValue<'ctx> {
+        // A lone temporary part is already a new string.
+        if let ([part], [_]) = (&self.parts[..], &self.temporaries[..]) {
+            return *part;
+        }
+
+        let string = concat(compiler, module, builder, &self.parts);
+
+        for temporary in self.temporaries {
+            free(module, builder, temporary);
+        }
+
+        string
+    }
+}
+
+/// Whether an expression that gives a string makes a new one that belongs to nothing else, so that
+/// it can be freed once it has been joined onto another string.
+pub(crate) fn is_temporary(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::BinOp { op: Op::Add, .. } | Expr::Interpolation(..)
+    )
+}
+
+/// Joins strings into a new one with a single call to the `string_concat` builtin, which is passed
+/// an array of the strings.
+pub(crate) fn concat<'ctx>(
+    compiler: &Compiler<'_, 'ctx>,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    parts: &[PointerValue<'ctx>],
+) -> PointerValue<'ctx> {
+    let context = compiler.context;
+    let string_type = context.i8_type().ptr_type(AddressSpace::default());
+    let array_type = string_type.array_type(parts.len() as u32);
+    let array = parts
+        .iter()
+        .enumerate()
+        .fold(array_type.get_undef(), |array, (index, part)| {
+            builder
+                .build_insert_value(array, *part, index as u32, "part")
+                .expect("index should be within the array")
+                .into_array_value()
+        });
+
+    let array_ptr = entry_alloca(context, builder, array_type, "parts");
+    builder.build_store(array_ptr, array);
+    let zero = context.i32_type().const_zero();
+    let first =
+        unsafe { builder.build_in_bounds_gep(array_type, array_ptr, &[zero, zero], "first_part") };
+    let count = context.i64_type().const_int(parts.len() as u64, false);
+
+    call_builtin(
+        module,
+        builder,
+        STRING_CONCAT,
+        &[first.into(), count.into()],
+    )
+    .into_pointer_value()
+}
+
+/// Frees a string created at runtime, which nothing may use afterwards.
+pub(crate) fn free<'ctx>(
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    string: PointerValue<'ctx>,
+) {
+    let function = module
+        .get_function(STRING_FREE)
+        .expect("builtins should be declared in every module");
+
+    builder.build_call(function, &[string.into()], "");
+}
+
+pub(super) fn call_builtin<'ctx>(
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    name: &str,
+    args: &[BasicMetadataValueEnum<'ctx>],
+) -> BasicValueEnum<'ctx> {
+    let function = module
+        .get_function(name)
+        .expect("builtins should be declared in every module");
+
+    builder
+        .build_call(function, args, "builtin")
+        .try_as_basic_value()
+        .left()
+        .expect("builtin should return a value")
+}
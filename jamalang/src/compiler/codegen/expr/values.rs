@@ -1,6 +1,11 @@
-use crate::parser::expr::{Bool, Char, Float, Identifier};
+use crate::parser::ast::Type;
+use crate::parser::expr::{Bool, Char, Float, Identifier, Integer, Str};
 
-use crate::compiler::codegen::{get_variable, value::Value, Codegen};
+use crate::compiler::codegen::{
+    get_pointer,
+    value::{unsigned_int_type, Value},
+    Codegen,
+};
 
 impl<'a, 'ctx> Codegen<'a, 'ctx> for Float {
     fn codegen(
@@ -20,7 +25,43 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for Float {
             >,
         >,
     ) -> crate::compiler::codegen::Value<'ctx> {
-        Value::Float(context.f32_type().const_float(self.0 as f64))
+        match self.1.expect("literals should be typed before codegen") {
+            Type::Float => Value::Float(context.f32_type().const_float(self.0)),
+            _ => Value::Double(context.f64_type().const_float(self.0)),
+        }
+    }
+}
+
+impl<'a, 'ctx> Codegen<'a, 'ctx> for Integer {
+    fn codegen(
+        self,
+        _: &'a crate::compiler::Compiler<'a, 'ctx>,
+        _: bool,
+        context: &'ctx inkwell::context::Context,
+        _: &'a inkwell::module::Module<'ctx>,
+        _: &'a inkwell::builder::Builder<'ctx>,
+        _: &'a mut Vec<
+            std::collections::HashMap<
+                String,
+                (
+                    inkwell::values::BasicValueEnum<'ctx>,
+                    inkwell::values::PointerValue<'ctx>,
+                ),
+            >,
+        >,
+    ) -> crate::compiler::codegen::Value<'ctx> {
+        match self.1.expect("literals should be typed before codegen") {
+            Type::UnsignedInt => {
+                Value::UnsignedInt(unsigned_int_type(context).const_named_struct(&[
+                    context.i32_type().const_int(self.0 as u64, false).into(),
+                ]))
+            }
+            Type::SignedInt => Value::SignedInt(context.i32_type().const_int(self.0 as u64, true)),
+            // Literals are never negative, but `-9223372036854775808` wraps around to `i64::MIN`.
+            Type::Float => Value::Float(context.f32_type().const_float(self.0 as u64 as f64)),
+            Type::Double => Value::Double(context.f64_type().const_float(self.0 as u64 as f64)),
+            _ => Value::Long(context.i64_type().const_int(self.0 as u64, true)),
+        }
     }
 }
 
@@ -46,6 +87,32 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for Char {
     }
 }
 
+impl<'a, 'ctx> Codegen<'a, 'ctx> for Str {
+    fn codegen(
+        self,
+        _: &'a crate::compiler::Compiler<'a, 'ctx>,
+        _: bool,
+        _: &'ctx inkwell::context::Context,
+        _: &'a inkwell::module::Module<'ctx>,
+        builder: &'a inkwell::builder::Builder<'ctx>,
+        _: &'a mut Vec<
+            std::collections::HashMap<
+                String,
+                (
+                    inkwell::values::BasicValueEnum<'ctx>,
+                    inkwell::values::PointerValue<'ctx>,
+                ),
+            >,
+        >,
+    ) -> crate::compiler::codegen::Value<'ctx> {
+        Value::String(
+            builder
+                .build_global_string_ptr(&self.0, "string")
+                .as_pointer_value(),
+        )
+    }
+}
+
 impl<'a, 'ctx> Codegen<'a, 'ctx> for Bool {
     fn codegen(
         self,
@@ -71,7 +138,7 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for Bool {
 impl<'a, 'ctx> Codegen<'a, 'ctx> for Identifier {
     fn codegen(
         self,
-        _: &'a crate::compiler::Compiler<'a, 'ctx>,
+        compiler: &'a crate::compiler::Compiler<'a, 'ctx>,
         _: bool,
         _: &'ctx inkwell::context::Context,
         _: &'a inkwell::module::Module<'ctx>,
@@ -86,9 +153,15 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for Identifier {
             >,
         >,
     ) -> crate::compiler::codegen::Value<'ctx> {
-        let (ty, ptr) = get_variable(variables, &self.0);
+        let (ty, ptr) =
+            get_pointer(compiler, builder, variables, &self.0, &self.1).unwrap_or_else(|| {
+                panic!(
+                    "{}: Attempted to access unknown variable {}",
+                    self.1, self.0
+                )
+            });
         builder
-            .build_load(ty.get_type(), *ptr, &format!("load_{}", self.0))
+            .build_load(ty, ptr, &format!("load_{}", self.0))
             .into()
     }
 }
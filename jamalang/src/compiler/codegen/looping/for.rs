@@ -6,7 +6,7 @@ use inkwell::{
     values::{ArrayValue, PointerValue},
 };
 
-use crate::compiler::codegen::{stack_top, value::Value, Codegen};
+use crate::compiler::codegen::{check_type_hint, entry_alloca, stack_top, value::Value, Codegen};
 
 impl<'a, 'ctx> Codegen<'a, 'ctx> for ForLoop {
     fn codegen(
@@ -33,13 +33,24 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for ForLoop {
             .into_basic_value()
             .into_array_value();
 
-        let index_ptr = builder.build_alloca(
+        if let Some(hint) = &self.identifier_type_hint {
+            check_type_hint(
+                compiler,
+                hint,
+                array.get_type().get_element_type(),
+                self.expression.span(),
+            );
+        }
+
+        let index_ptr = entry_alloca(
+            context,
+            builder,
             context.i32_type(),
             &format!("jamalang_internal$array_index_{}", self.identifier),
         );
         builder.build_store(index_ptr, context.i32_type().const_int(0u64, false));
 
-        let array_ptr = builder.build_alloca(array.get_type(), "array_alloc");
+        let array_ptr = entry_alloca(context, builder, array.get_type(), "array_alloc");
         builder.build_store(array_ptr, array);
 
         let current_block = builder
@@ -99,6 +110,7 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for ForLoop {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn loop_meta_instructions<'a, 'ctx>(
     context: &'ctx Context,
     builder: &'a Builder<'ctx>,
@@ -139,7 +151,7 @@ fn loop_meta_instructions<'a, 'ctx>(
     if let Some((_, ptr)) = stack_top!(variables).get(identifier) {
         builder.build_store(*ptr, element);
     } else {
-        let ptr = builder.build_alloca(element.get_type(), &identifier);
+        let ptr = entry_alloca(context, builder, element.get_type(), identifier);
         builder.build_store(ptr, element);
         stack_top!(variables).insert(identifier.clone(), (element, ptr));
     };
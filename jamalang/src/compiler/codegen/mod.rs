@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use crate::diagnostic::Span;
 use crate::parser::ast::{
     Assignment, Expr, ForLoop, ForeignModule, FunctionDefinition, IfStatement, Return, Statement,
     Type, WhileLoop,
@@ -8,13 +9,14 @@ use inkwell::{
     builder::Builder,
     context::Context,
     module::Module,
-    types::{BasicMetadataTypeEnum, BasicTypeEnum},
+    types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum},
     values::{BasicValueEnum, PointerValue},
+    AddressSpace,
 };
 
 use crate::compiler::Compiler;
 
-use self::value::Value;
+use self::value::{is_unsigned_int, unsigned_int_type, Value};
 
 mod basic;
 mod control_flow;
@@ -32,45 +34,188 @@ macro_rules! stack_top {
 
 pub(crate) use stack_top;
 
-pub(crate) fn get_variable<'a, 'ctx>(
-    variables: &'a Vec<HashMap<String, (BasicValueEnum<'ctx>, PointerValue<'ctx>)>>,
+fn find_variable<'a, 'ctx>(
+    variables: &'a [HashMap<String, (BasicValueEnum<'ctx>, PointerValue<'ctx>)>],
     identifier: &str,
-) -> &'a (BasicValueEnum<'ctx>, PointerValue<'ctx>) {
-    for scope in variables.iter().rev() {
-        if let Some(value) = scope.get(identifier) {
-            return value;
-        }
+) -> Option<&'a (BasicValueEnum<'ctx>, PointerValue<'ctx>)> {
+    variables
+        .iter()
+        .rev()
+        .find_map(|scope| scope.get(identifier))
+}
+
+/// Finds the value a dotted identifier such as `p.x` refers to, which is either a variable or a
+/// field of a record stored in one. Returns the type of the value and a pointer to it, or `None`
+/// if the identifier doesn't start with the name of a variable.
+pub(crate) fn get_pointer<'ctx>(
+    compiler: &Compiler<'_, 'ctx>,
+    builder: &Builder<'ctx>,
+    variables: &[HashMap<String, (BasicValueEnum<'ctx>, PointerValue<'ctx>)>],
+    identifier: &str,
+    span: &Span,
+) -> Option<(BasicTypeEnum<'ctx>, PointerValue<'ctx>)> {
+    // Imported variables have dots in their names, so the longest matching prefix wins.
+    let (variable, fields) = std::iter::once(identifier.len())
+        .chain(identifier.rmatch_indices('.').map(|(index, _)| index))
+        .find_map(|end| {
+            find_variable(variables, &identifier[..end])
+                .map(|variable| (variable, identifier[end..].split('.').skip(1)))
+        })?;
+    let (mut ty, mut ptr) = (variable.0.get_type(), variable.1);
+
+    for field in fields {
+        let BasicTypeEnum::StructType(record) = ty else {
+            panic!("{}: `{}` is not a record", span, identifier)
+        };
+        let index = compiler
+            .record_fields(record)
+            .and_then(|names| names.iter().position(|name| name == field))
+            .unwrap_or_else(|| panic!("{}: Record has no field `{}`", span, field));
+
+        ptr = builder
+            .build_struct_gep(record, ptr, index as u32, &format!("field_{}", field))
+            .expect("field index should be valid");
+        ty = record
+            .get_field_type_at_index(index as u32)
+            .expect("field index should be valid");
     }
 
-    panic!("Attempted to access unknown variable {}", identifier)
+    Some((ty, ptr))
 }
 
-pub(crate) fn get_any_type_from_type_hint<'ctx>(
+/// Allocates stack space in the entry block of the function being generated rather than where the
+/// builder is, so that code inside a loop reuses the same space on every iteration instead of
+/// growing the stack.
+pub(crate) fn entry_alloca<'ctx>(
     context: &'ctx Context,
+    builder: &Builder<'ctx>,
+    ty: impl BasicType<'ctx>,
+    name: &str,
+) -> PointerValue<'ctx> {
+    let entry = builder
+        .get_insert_block()
+        .and_then(|block| block.get_parent())
+        .and_then(|function| function.get_first_basic_block())
+        .expect("function should be present");
+    let entry_builder = context.create_builder();
+
+    match entry.get_first_instruction() {
+        Some(first) => entry_builder.position_before(&first),
+        None => entry_builder.position_at_end(entry),
+    }
+
+    entry_builder.build_alloca(ty, name)
+}
+
+pub(crate) fn get_any_type_from_type_hint<'ctx>(
+    compiler: &Compiler<'_, 'ctx>,
     hint: Type,
 ) -> BasicTypeEnum<'ctx> {
+    let context = compiler.context;
+
     match hint {
         Type::Char => BasicTypeEnum::IntType(context.i8_type()),
         Type::Bool => BasicTypeEnum::IntType(context.bool_type()),
-        Type::Number => BasicTypeEnum::FloatType(context.f32_type()),
-        //TODO: Real void types?
-        Type::Void => BasicTypeEnum::IntType(context.bool_type()),
-        _ => todo!("Type {:#?} is not yet supported", hint),
+        Type::Float => BasicTypeEnum::FloatType(context.f32_type()),
+        Type::Double => BasicTypeEnum::FloatType(context.f64_type()),
+        Type::UnsignedInt => BasicTypeEnum::StructType(unsigned_int_type(context)),
+        Type::SignedInt => BasicTypeEnum::IntType(context.i32_type()),
+        Type::Long => BasicTypeEnum::IntType(context.i64_type()),
+        Type::String => {
+            BasicTypeEnum::PointerType(context.i8_type().ptr_type(AddressSpace::default()))
+        }
+        Type::Record(fields) => BasicTypeEnum::StructType(
+            compiler.record_type(
+                &fields
+                    .into_iter()
+                    .map(|(name, hint)| (name, get_any_type_from_type_hint(compiler, hint)))
+                    .collect::<Vec<_>>(),
+            ),
+        ),
+        Type::Array { len, kind } => match get_any_type_from_type_hint(compiler, *kind) {
+            BasicTypeEnum::ArrayType(kind) => kind.array_type(len).into(),
+            BasicTypeEnum::FloatType(kind) => kind.array_type(len).into(),
+            BasicTypeEnum::IntType(kind) => kind.array_type(len).into(),
+            BasicTypeEnum::PointerType(kind) => kind.array_type(len).into(),
+            BasicTypeEnum::StructType(kind) => kind.array_type(len).into(),
+            BasicTypeEnum::VectorType(kind) => kind.array_type(len).into(),
+        },
+        Type::Void => unreachable!("`void` has no values, so nothing can have it as a type"),
+        Type::Custom(name) => unreachable!("the unknown type `{}` should have been reported", name),
+    }
+}
+
+/// Returns the type a value of the given LLVM type has in the language, for use in errors.
+pub(crate) fn type_of<'ctx>(compiler: &Compiler<'_, 'ctx>, ty: BasicTypeEnum<'ctx>) -> Type {
+    match ty {
+        BasicTypeEnum::FloatType(float_type) if float_type == compiler.context.f64_type() => {
+            Type::Double
+        }
+        BasicTypeEnum::FloatType(_) => Type::Float,
+        BasicTypeEnum::IntType(int_type) => match int_type.get_bit_width() {
+            1 => Type::Bool,
+            8 => Type::Char,
+            32 => Type::SignedInt,
+            64 => Type::Long,
+            width => unreachable!("the language has no {}-bit integers", width),
+        },
+        BasicTypeEnum::PointerType(_) => Type::String,
+        BasicTypeEnum::ArrayType(array_type) => Type::Array {
+            len: array_type.len(),
+            kind: Box::new(type_of(compiler, array_type.get_element_type())),
+        },
+        BasicTypeEnum::StructType(struct_type) if is_unsigned_int(struct_type) => Type::UnsignedInt,
+        BasicTypeEnum::StructType(struct_type) => Type::Record(
+            compiler
+                .record_fields(struct_type)
+                .expect("records should have field names")
+                .into_iter()
+                .zip(struct_type.get_field_types())
+                .map(|(name, ty)| (name, type_of(compiler, ty)))
+                .collect(),
+        ),
+        BasicTypeEnum::VectorType(_) => unreachable!("the language has no vector types"),
+    }
+}
+
+/// Panics if a value doesn't have the type it was declared with.
+pub(crate) fn check_type_hint<'ctx>(
+    compiler: &Compiler<'_, 'ctx>,
+    hint: &Type,
+    found: BasicTypeEnum<'ctx>,
+    span: &Span,
+) {
+    if get_any_type_from_type_hint(compiler, hint.clone()) != found {
+        panic!(
+            "{}: Expected a value of type {}, found {}",
+            span,
+            hint,
+            type_of(compiler, found)
+        )
     }
 }
 
 pub(crate) fn get_basic_type_metadata_from_type_hint<'ctx>(
-    context: &'ctx Context,
+    compiler: &Compiler<'_, 'ctx>,
     hint: Type,
 ) -> BasicMetadataTypeEnum<'ctx> {
+    let context = compiler.context;
+
     match hint {
         Type::Char => BasicMetadataTypeEnum::IntType(context.i8_type()),
         Type::Bool => BasicMetadataTypeEnum::IntType(context.bool_type()),
-        Type::Number => BasicMetadataTypeEnum::FloatType(context.f32_type()),
-        // Type::Array(array_type) => BasicMetadataTypeEnum::ArrayType(match get_basic_type_metadata_from_type_hint(context, array_type) {
-        // BasicMetadataTypeEnum::ArrayType(array_type) => array_type.array_type(size)
-        // }),
-        _ => todo!("Type {:#?} is not yet supported", hint),
+        Type::Float => BasicMetadataTypeEnum::FloatType(context.f32_type()),
+        Type::String => {
+            BasicMetadataTypeEnum::PointerType(context.i8_type().ptr_type(AddressSpace::default()))
+        }
+        Type::Double
+        | Type::UnsignedInt
+        | Type::SignedInt
+        | Type::Long
+        | Type::Record(_)
+        | Type::Array { .. } => get_any_type_from_type_hint(compiler, hint).into(),
+        Type::Void => unreachable!("`void` has no values, so nothing can have it as a type"),
+        Type::Custom(name) => unreachable!("the unknown type `{}` should have been reported", name),
     }
 }
 
@@ -121,7 +266,7 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for Statement {
                 let value: IfStatement = self.try_into().unwrap();
                 value.codegen(compiler, main_function, context, module, builder, variables)
             }
-            Statement::ForeignModule(_) => {
+            Statement::ForeignModule { .. } => {
                 let value: ForeignModule = self.try_into().unwrap();
                 value.codegen(compiler, main_function, context, module, builder, variables)
             }
@@ -131,7 +276,7 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> for Statement {
                     .expr
                     .codegen(compiler, main_function, context, module, builder, variables)
             }
-            Statement::Comment(_) => Value::Void,
+            Statement::Comment(..) => Value::Void,
         }
     }
 }
@@ -2,44 +2,96 @@ use inkwell::{
     builder::Builder,
     context::Context,
     module::Module,
-    values::{ArrayValue, BasicValueEnum, FloatValue, GlobalValue, IntValue, PointerValue},
+    types::{BasicTypeEnum, StructType},
+    values::{
+        ArrayValue, BasicValueEnum, FloatValue, GlobalValue, IntValue, PointerValue, StructValue,
+    },
+    AddressSpace,
 };
 
+use super::entry_alloca;
+
+/// The name of the struct type [`Value::UnsignedInt`]s are wrapped in.
+pub(crate) const UNSIGNED_INT: &str = "UnsignedInt";
+
+/// Returns the struct type that wraps an unsigned integer, so that it has a different LLVM type to
+/// a signed one.
+pub(crate) fn unsigned_int_type(context: &Context) -> StructType<'_> {
+    context.get_struct_type(UNSIGNED_INT).unwrap_or_else(|| {
+        let unsigned = context.opaque_struct_type(UNSIGNED_INT);
+        unsigned.set_body(&[context.i32_type().into()], false);
+        unsigned
+    })
+}
+
+/// Returns the `i32` inside an unsigned integer.
+pub(crate) fn unwrap_unsigned<'ctx>(
+    builder: &Builder<'ctx>,
+    value: StructValue<'ctx>,
+) -> IntValue<'ctx> {
+    builder
+        .build_extract_value(value, 0, "unsigned")
+        .expect("unsigned integers should hold an i32")
+        .into_int_value()
+}
+
+/// Wraps an `i32` up as an unsigned integer.
+pub(crate) fn wrap_unsigned<'ctx>(
+    context: &'ctx Context,
+    builder: &Builder<'ctx>,
+    value: IntValue<'ctx>,
+) -> StructValue<'ctx> {
+    builder
+        .build_insert_value(unsigned_int_type(context).get_undef(), value, 0, "unsigned")
+        .expect("unsigned integers should hold an i32")
+        .into_struct_value()
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Value<'ctx> {
+    /// A 32-bit float.
     Float(FloatValue<'ctx>),
+    /// A 64-bit float.
+    Double(FloatValue<'ctx>),
+    /// A 32-bit unsigned integer. LLVM integers don't have a sign, so the `i32` is wrapped in a
+    /// struct to tell it apart from a [`Value::SignedInt`].
+    UnsignedInt(StructValue<'ctx>),
+    SignedInt(IntValue<'ctx>),
+    Long(IntValue<'ctx>),
     Bool(IntValue<'ctx>),
     Char(IntValue<'ctx>),
+    /// A pointer to null-terminated UTF-8. Literals are constants, and strings created at
+    /// runtime live on the heap.
+    String(PointerValue<'ctx>),
     Array(ArrayValue<'ctx>),
+    /// An anonymous record, whose fields are sorted by name.
+    Record(StructValue<'ctx>),
     Void,
 }
 
 impl<'a, 'ctx> Value<'ctx> {
+    /// Allocates a variable that can hold the value in the entry block of the current function.
     pub fn alloca(
         &self,
         context: &'ctx Context,
         builder: &'a Builder<'ctx>,
         identifier: &str,
     ) -> PointerValue<'ctx> {
-        match self {
-            Value::Float(_) => {
-                builder.build_alloca(context.f32_type(), &format!("alloca_{}", identifier))
-            }
-            Value::Bool(_) => {
-                builder.build_alloca(context.bool_type(), &format!("alloca_{}", identifier))
-            }
-            Value::Char(_) => {
-                builder.build_alloca(context.i8_type(), &format!("alloca_{}", identifier))
-            }
-            Value::Array(array) => builder.build_array_alloca(
-                array.get_type(),
-                context
-                    .i32_type()
-                    .const_int(array.get_type().len() as u64, false),
-                &format!("alloca_{}", identifier),
-            ),
+        let ty: BasicTypeEnum = match self {
+            Value::Float(_) => context.f32_type().into(),
+            Value::Double(_) => context.f64_type().into(),
+            Value::UnsignedInt(_) => unsigned_int_type(context).into(),
+            Value::SignedInt(_) => context.i32_type().into(),
+            Value::Long(_) => context.i64_type().into(),
+            Value::Bool(_) => context.bool_type().into(),
+            Value::Char(_) => context.i8_type().into(),
+            Value::String(_) => context.i8_type().ptr_type(AddressSpace::default()).into(),
+            Value::Array(array) => array.get_type().into(),
+            Value::Record(record) => record.get_type().into(),
             Value::Void => panic!("Cannot allocate void"),
-        }
+        };
+
+        entry_alloca(context, builder, ty, &format!("alloca_{}", identifier))
     }
 
     pub fn global_alloca(
@@ -52,25 +104,62 @@ impl<'a, 'ctx> Value<'ctx> {
             Value::Float(_) => {
                 module.add_global(context.f32_type(), None, &format!("global_{}", identifier))
             }
+            Value::Double(_) => {
+                module.add_global(context.f64_type(), None, &format!("global_{}", identifier))
+            }
+            Value::UnsignedInt(_) => module.add_global(
+                unsigned_int_type(context),
+                None,
+                &format!("global_{}", identifier),
+            ),
+            Value::SignedInt(_) => {
+                module.add_global(context.i32_type(), None, &format!("global_{}", identifier))
+            }
+            Value::Long(_) => {
+                module.add_global(context.i64_type(), None, &format!("global_{}", identifier))
+            }
             Value::Bool(_) => {
                 module.add_global(context.bool_type(), None, &format!("global_{}", identifier))
             }
             Value::Char(_) => {
                 module.add_global(context.i8_type(), None, &format!("global_{}", identifier))
             }
+            Value::String(_) => module.add_global(
+                context.i8_type().ptr_type(AddressSpace::default()),
+                None,
+                &format!("global_{}", identifier),
+            ),
             Value::Array(array) => {
                 module.add_global(array.get_type(), None, &format!("global_{}", identifier))
             }
+            Value::Record(record) => {
+                module.add_global(record.get_type(), None, &format!("global_{}", identifier))
+            }
             Value::Void => panic!("Cannot allocate void"),
         }
     }
 
+    /// Returns the type of the value, or `None` if it is void.
+    pub fn get_type(&self) -> Option<BasicTypeEnum<'ctx>> {
+        match self {
+            Value::Void => None,
+            value => Some(value.into_basic_value().get_type()),
+        }
+    }
+
     pub fn into_basic_value(self) -> BasicValueEnum<'ctx> {
         match self {
-            Value::Float(float_value) => BasicValueEnum::FloatValue(float_value),
+            Value::Float(float_value) | Value::Double(float_value) => {
+                BasicValueEnum::FloatValue(float_value)
+            }
+            Value::UnsignedInt(unsigned_value) => BasicValueEnum::StructValue(unsigned_value),
+            Value::SignedInt(int_value) => BasicValueEnum::IntValue(int_value),
+            Value::Long(long_value) => BasicValueEnum::IntValue(long_value),
             Value::Bool(bool_value) => BasicValueEnum::IntValue(bool_value),
             Value::Char(char_value) => BasicValueEnum::IntValue(char_value),
+            Value::String(string_value) => BasicValueEnum::PointerValue(string_value),
             Value::Array(array_value) => BasicValueEnum::ArrayValue(array_value),
+            Value::Record(record_value) => BasicValueEnum::StructValue(record_value),
             Value::Void => panic!("Cannot convert void to basic value"),
         }
     }
@@ -79,16 +168,37 @@ impl<'a, 'ctx> Value<'ctx> {
 impl<'ctx> From<BasicValueEnum<'ctx>> for Value<'ctx> {
     fn from(value: BasicValueEnum<'ctx>) -> Self {
         match value {
-            BasicValueEnum::FloatValue(float_value) => Value::Float(float_value),
-            BasicValueEnum::IntValue(int_value) => {
-                if int_value.get_type().get_bit_width() == 1 {
-                    Value::Bool(int_value)
+            BasicValueEnum::FloatValue(float_value) => {
+                let float_type = float_value.get_type();
+                if float_type == float_type.get_context().f64_type() {
+                    Value::Double(float_value)
                 } else {
-                    Value::Char(int_value)
+                    Value::Float(float_value)
                 }
             }
+            BasicValueEnum::IntValue(int_value) => match int_value.get_type().get_bit_width() {
+                1 => Value::Bool(int_value),
+                8 => Value::Char(int_value),
+                32 => Value::SignedInt(int_value),
+                64 => Value::Long(int_value),
+                width => unreachable!("Unexpected integer width {}", width),
+            },
             BasicValueEnum::ArrayValue(array_value) => Value::Array(array_value),
+            BasicValueEnum::PointerValue(string_value) => Value::String(string_value),
+            BasicValueEnum::StructValue(struct_value)
+                if is_unsigned_int(struct_value.get_type()) =>
+            {
+                Value::UnsignedInt(struct_value)
+            }
+            BasicValueEnum::StructValue(record_value) => Value::Record(record_value),
             _ => unreachable!("Unexpected basic value {:#?}", value),
         }
     }
 }
+
+/// Whether a struct type is the wrapper of an unsigned integer rather than a record.
+pub(crate) fn is_unsigned_int(struct_type: StructType) -> bool {
+    struct_type
+        .get_name()
+        .is_some_and(|name| name.to_bytes() == UNSIGNED_INT.as_bytes())
+}
@@ -1,14 +1,23 @@
-use std::{collections::HashMap, ffi::c_char};
+use std::{cell::RefCell, collections::HashMap, ffi::CString, rc::Rc};
 
-use crate::compiler::codegen::Codegen;
-use crate::parser::JamalangFile;
+use crate::analysis;
+use crate::compiler::codegen::{
+    get_any_type_from_type_hint, get_basic_type_metadata_from_type_hint, Codegen,
+};
+use crate::diagnostic::Diagnostic;
+use crate::parser::{
+    ast::{Statement, Type},
+    imports::namespace,
+    JamalangFile,
+};
 use builtins::{builtins, Builtin};
 use inkwell::{
     builder::Builder,
     context::Context,
     memory_buffer::MemoryBuffer,
     module::{Linkage, Module},
-    values::{BasicValueEnum, PointerValue},
+    types::{BasicType, BasicTypeEnum, FunctionType, StructType},
+    values::{BasicValueEnum, FunctionValue, PointerValue},
 };
 use llvm_sys::support::LLVMAddSymbol;
 
@@ -17,17 +26,46 @@ mod codegen;
 
 const STDLIB: &[u8] = include_bytes!("../stdlib.bc");
 
+pub(crate) fn initializer_name(namespace: &str) -> String {
+    format!("jamalang_internal${}$init", namespace)
+}
+
 pub struct Compiler<'a, 'ctx> {
     pub context: &'ctx Context,
     pub builder: &'a Builder<'ctx>,
     pub module: &'a Module<'ctx>,
     pub builtins: Vec<Builtin<'ctx>>,
+    /// The prefix of every symbol defined by the file being compiled, `None` for the entry file.
+    pub namespace: Option<String>,
+    /// Whether array accesses are checked to be in bounds at runtime.
+    pub bounds_checks: bool,
+    /// The imported files compiled so far, shared with the compilers of those files.
+    pub(crate) imports: Rc<RefCell<Imports<'ctx>>>,
+    /// The field names of each record type, keyed by the name of its struct type.
+    pub(crate) records: Rc<RefCell<HashMap<String, Vec<String>>>>,
 }
 
 pub struct CompileResult<'ctx> {
     pub variables: HashMap<String, (BasicValueEnum<'ctx>, PointerValue<'ctx>)>,
 }
 
+#[derive(Default)]
+pub(crate) struct Imports<'ctx> {
+    /// Functions that every module can call, but which are only defined in the entry module.
+    pub(crate) externals: Vec<(String, FunctionType<'ctx>)>,
+    /// The symbols defined by each compiled file, keyed by its canonical path.
+    pub(crate) exports: HashMap<String, Exports<'ctx>>,
+}
+
+/// The symbols an imported file makes available to the files importing it.
+#[derive(Clone)]
+pub(crate) struct Exports<'ctx> {
+    pub(crate) namespace: String,
+    pub(crate) functions: Vec<(String, FunctionType<'ctx>)>,
+    /// The name importers refer to each top-level variable by, its type, and its symbol.
+    pub(crate) globals: Vec<(String, BasicTypeEnum<'ctx>, String)>,
+}
+
 impl<'a, 'ctx> Compiler<'a, 'ctx> {
     pub fn new(
         context: &'ctx Context,
@@ -39,12 +77,26 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
             builder,
             module,
             builtins: builtins(context),
+            namespace: None,
+            bounds_checks: true,
+            imports: Rc::new(RefCell::new(Imports::default())),
+            records: Rc::new(RefCell::new(HashMap::new())),
         }
     }
 
-    pub fn compile(&mut self, file: JamalangFile) -> CompileResult {
+    /// Compiles the entry file, returning every error found in it or the files it imports.
+    ///
+    /// The whole program is checked before any code is generated, so nothing is generated for a
+    /// program with errors.
+    pub fn compile(
+        &mut self,
+        mut file: JamalangFile,
+    ) -> Result<CompileResult<'ctx>, Vec<Diagnostic>> {
+        analysis::check(&mut file)?;
+
         for builtin in &self.builtins {
-            unsafe { LLVMAddSymbol(builtin.name.as_ptr() as *const c_char, builtin.function) }
+            let name = CString::new(builtin.name.as_str()).expect("builtin names should be valid");
+            unsafe { LLVMAddSymbol(name.as_ptr(), builtin.function) }
             self.module
                 .add_function(&builtin.name, builtin.f_type, Some(Linkage::External));
         }
@@ -58,9 +110,75 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
         );
         let block = self.context.append_basic_block(main, "entry");
         self.builder.position_at_end(block);
+
+        self.declare_functions(&file.statements);
+        let variables = self.compile_statements(file.statements);
+        self.builder.build_return(None);
+
+        Ok(CompileResult { variables })
+    }
+
+    /// Compiles an imported file into an initializer function, which runs the file's top-level
+    /// code the first time it is called and does nothing afterwards.
+    pub(crate) fn compile_import(&self, file: JamalangFile) -> CompileResult<'ctx> {
+        let namespace = self
+            .namespace
+            .as_deref()
+            .expect("imported files should have a namespace");
+
+        for (name, f_type) in &self.imports.borrow().externals {
+            self.module
+                .add_function(name, *f_type, Some(Linkage::External));
+        }
+
+        let init = self.module.add_function(
+            &initializer_name(namespace),
+            self.context.void_type().fn_type(&[], false),
+            None,
+        );
+        let initialized = self.module.add_global(
+            self.context.bool_type(),
+            None,
+            &format!("jamalang_internal${}$initialized", namespace),
+        );
+        initialized.set_initializer(&self.context.bool_type().const_zero());
+
+        let check = self.context.append_basic_block(init, "check");
+        let body = self.context.append_basic_block(init, "body");
+        let done = self.context.append_basic_block(init, "done");
+
+        self.builder.position_at_end(check);
+        let is_initialized = self.builder.build_load(
+            self.context.bool_type(),
+            initialized.as_pointer_value(),
+            "load_initialized",
+        );
+        self.builder
+            .build_conditional_branch(is_initialized.into_int_value(), done, body);
+
+        self.builder.position_at_end(done);
+        self.builder.build_return(None);
+
+        self.builder.position_at_end(body);
+        self.builder.build_store(
+            initialized.as_pointer_value(),
+            self.context.bool_type().const_int(1, false),
+        );
+
+        self.declare_functions(&file.statements);
+        let variables = self.compile_statements(file.statements);
+        self.builder.build_return(None);
+
+        CompileResult { variables }
+    }
+
+    fn compile_statements(
+        &self,
+        statements: Vec<Statement>,
+    ) -> HashMap<String, (BasicValueEnum<'ctx>, PointerValue<'ctx>)> {
         let mut variables = vec![HashMap::new()];
 
-        for statement in file.statements {
+        for statement in statements {
             statement.codegen(
                 self,
                 true,
@@ -71,15 +189,120 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
             );
         }
 
-        self.builder.build_return(None);
+        variables
+            .pop()
+            .expect("scopes should hold at least 1 value")
+    }
+
+    /// Declares every function defined by some statements, however deeply it is nested, and by the
+    /// files they import, so that functions can be called before their bodies are compiled.
+    fn declare_functions(&self, statements: &[Statement]) {
+        for_each_function(statements, &mut |identifier, parameters, return_type| {
+            let name = self.mangle(identifier);
 
-        CompileResult {
-            variables: variables
-                .pop()
-                .expect("scopes should hold at least 1 value"),
+            // Analysis reports any function defined twice, so a name can only have been declared
+            // already by the standard library.
+            if self.module.get_function(&name).is_none() {
+                let fn_type = self.function_type(parameters, return_type);
+                self.module.add_function(&name, fn_type, None);
+            }
+        });
+
+        // Imported functions are defined when their file is compiled and linked in, at the import.
+        for statement in statements {
+            let Statement::ForeignModule {
+                path,
+                file: Some(file),
+                ..
+            } = statement
+            else {
+                continue;
+            };
+            let namespace = namespace(path);
+
+            for_each_function(
+                &file.statements,
+                &mut |identifier, parameters, return_type| {
+                    let name = format!("{}.{}", namespace, identifier);
+
+                    if self.module.get_function(&name).is_none() {
+                        let fn_type = self.function_type(parameters, return_type);
+                        self.module
+                            .add_function(&name, fn_type, Some(Linkage::External));
+                    }
+                },
+            );
         }
     }
 
+    /// Returns the LLVM type of a function with the given parameters and return type.
+    pub(crate) fn function_type(
+        &self,
+        parameters: &[(String, Type)],
+        return_type: &Type,
+    ) -> FunctionType<'ctx> {
+        let parameters = parameters
+            .iter()
+            .map(|(_, hint)| get_basic_type_metadata_from_type_hint(self, hint.clone()))
+            .collect::<Vec<_>>();
+
+        match return_type {
+            Type::Void => self.context.void_type().fn_type(&parameters, false),
+            return_type => {
+                get_any_type_from_type_hint(self, return_type.clone()).fn_type(&parameters, false)
+            }
+        }
+    }
+
+    /// Returns the symbol name of something defined at the top level of the file being compiled.
+    pub(crate) fn mangle(&self, identifier: &str) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("{}.{}", namespace, identifier),
+            None => identifier.to_owned(),
+        }
+    }
+
+    /// Looks up a function, preferring those defined by the file being compiled.
+    pub(crate) fn get_function(&self, identifier: &str) -> Option<FunctionValue<'ctx>> {
+        self.module
+            .get_function(&self.mangle(identifier))
+            .or_else(|| self.module.get_function(identifier))
+    }
+
+    /// Returns the struct type of the record with the given fields, which must be sorted by name.
+    ///
+    /// Records with the same field names and types share a struct type, so they are compatible
+    /// wherever they were created.
+    pub(crate) fn record_type(&self, fields: &[(String, BasicTypeEnum<'ctx>)]) -> StructType<'ctx> {
+        let name = format!(
+            "record{{{}}}",
+            fields
+                .iter()
+                .map(|(name, ty)| format!("{}: {}", name, ty.print_to_string().to_string_lossy()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let record = self.context.get_struct_type(&name).unwrap_or_else(|| {
+            let record = self.context.opaque_struct_type(&name);
+            let types = fields.iter().map(|(_, ty)| *ty).collect::<Vec<_>>();
+            record.set_body(&types, false);
+            record
+        });
+
+        self.records
+            .borrow_mut()
+            .insert(name, fields.iter().map(|(name, _)| name.clone()).collect());
+
+        record
+    }
+
+    /// Returns the field names of a struct type created by [`Compiler::record_type`].
+    pub(crate) fn record_fields(&self, record: StructType<'ctx>) -> Option<Vec<String>> {
+        let name = record.get_name()?.to_string_lossy();
+        self.records.borrow().get(name.as_ref()).cloned()
+    }
+
     fn link_stdlib(&self) {
         let memory_buffer = MemoryBuffer::create_from_memory_range(STDLIB, "stdlib");
         let module = self
@@ -90,5 +313,55 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
         self.module
             .link_in_module(module)
             .expect("stdlib should be linkable");
+
+        self.imports.borrow_mut().externals = self
+            .module
+            .get_functions()
+            .filter(|function| function.get_linkage() == Linkage::External)
+            .map(|function| {
+                (
+                    function.get_name().to_string_lossy().into_owned(),
+                    function.get_type(),
+                )
+            })
+            .collect();
+    }
+}
+
+/// Calls `f` with the name, parameters and return type of every function defined by some
+/// statements, however deeply it is nested, in the order they are written.
+fn for_each_function(statements: &[Statement], f: &mut impl FnMut(&str, &[(String, Type)], &Type)) {
+    for statement in statements {
+        match statement {
+            Statement::FunctionDefinition {
+                identifier,
+                parameters,
+                return_type_hint,
+                body,
+                ..
+            } => {
+                let return_type = return_type_hint
+                    .as_ref()
+                    .expect("return types should be inferred before codegen");
+                f(identifier, parameters, return_type);
+                for_each_function(body, f);
+            }
+            Statement::WhileLoop { body, .. } | Statement::ForLoop { body, .. } => {
+                for_each_function(body, f)
+            }
+            Statement::IfStatement {
+                body,
+                else_ifs,
+                else_body,
+                ..
+            } => {
+                for_each_function(body, f);
+                for (_, body) in else_ifs {
+                    for_each_function(body, f);
+                }
+                for_each_function(else_body, f);
+            }
+            _ => (),
+        }
     }
 }
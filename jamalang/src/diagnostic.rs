@@ -0,0 +1,280 @@
+use std::{fmt, sync::Arc};
+
+use crate::parser::Rule;
+
+/// A region of a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub file: Arc<str>,
+    /// The byte offset of the first character.
+    pub start: usize,
+    /// The byte offset one past the last character.
+    pub end: usize,
+    /// The 1-based line of the first character.
+    pub line: usize,
+    /// The 1-based column of the first character, in characters.
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(file: &Arc<str>, span: pest::Span) -> Self {
+        let (line, column) = span.start_pos().line_col();
+
+        Self {
+            file: file.clone(),
+            start: span.start(),
+            end: span.end(),
+            line,
+            column,
+        }
+    }
+
+    /// Creates a span covering both `self` and `other`, which must follow it in the same file.
+    pub fn to(&self, other: &Span) -> Self {
+        Self {
+            end: other.end.max(self.end),
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// An invariant of the compiler itself was broken.
+    Internal,
+    /// The source does not match the grammar.
+    Syntax,
+    /// The construct is valid syntax, but is not supported by the compiler yet.
+    Unsupported,
+    /// An imported file could not be found or read.
+    ImportNotFound,
+    /// A file imports itself, directly or through other files.
+    ImportCycle,
+    /// Two different imported files have the same name, so their names would clash.
+    NamespaceCollision,
+    /// A record literal or type names the same field twice.
+    DuplicateField,
+    /// A value has a different type to the one it is used as.
+    TypeMismatch,
+    /// A value is cast to a type it can't be converted to.
+    InvalidCast,
+    /// A numeric literal is too large for its type.
+    LiteralOverflow,
+    /// A field is accessed on a value that doesn't have it.
+    UnknownField,
+    /// A variable is used that isn't defined where it is used.
+    UndefinedVariable,
+    /// A function is called that doesn't exist.
+    UndefinedFunction,
+    /// A function is called with the wrong number of arguments.
+    ArgumentCount,
+    /// A function that returns a value can reach its end without a `return`.
+    MissingReturn,
+    /// A function without a written return type calls itself before any of its `return`s.
+    UnknownReturnType,
+    /// A parameter is written without a type.
+    MissingTypeHint,
+    /// A variable, parameter or field would hold a `void`, which has no value.
+    VoidValue,
+    /// A type hint or cast names a type that doesn't exist.
+    UnknownType,
+    /// An imported file's name isn't an identifier, so its names couldn't be used.
+    InvalidNamespace,
+    /// A function is defined more than once, so only one of its definitions could be called.
+    Redefinition,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Internal => "E0000",
+            ErrorCode::Syntax => "E0001",
+            ErrorCode::Unsupported => "E0002",
+            ErrorCode::ImportNotFound => "E0003",
+            ErrorCode::ImportCycle => "E0004",
+            ErrorCode::DuplicateField => "E0005",
+            ErrorCode::TypeMismatch => "E0006",
+            ErrorCode::InvalidCast => "E0007",
+            ErrorCode::LiteralOverflow => "E0008",
+            ErrorCode::UnknownField => "E0009",
+            ErrorCode::UndefinedVariable => "E0010",
+            ErrorCode::UndefinedFunction => "E0011",
+            ErrorCode::ArgumentCount => "E0012",
+            ErrorCode::MissingReturn => "E0013",
+            ErrorCode::UnknownReturnType => "E0014",
+            ErrorCode::MissingTypeHint => "E0015",
+            ErrorCode::VoidValue => "E0016",
+            ErrorCode::NamespaceCollision => "E0017",
+            ErrorCode::UnknownType => "E0018",
+            ErrorCode::InvalidNamespace => "E0019",
+            ErrorCode::Redefinition => "E0020",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    /// Primary labels point at the cause of the error, secondary labels add context.
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub code: ErrorCode,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    /// Reports a broken assumption about the shape of the parse tree.
+    pub fn internal(span: Span, message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message).with_label(span, "while parsing this")
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: self.labels.iter().all(|label| !label.primary),
+        });
+        self
+    }
+
+    pub fn with_secondary_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn primary_span(&self) -> Option<&Span> {
+        self.labels
+            .iter()
+            .find(|label| label.primary)
+            .map(|label| &label.span)
+    }
+
+    pub fn from_pest(file: &Arc<str>, error: pest::error::Error<Rule>) -> Self {
+        let (start, end) = match error.location {
+            pest::error::InputLocation::Pos(pos) => (pos, pos),
+            pest::error::InputLocation::Span(span) => span,
+        };
+        let (line, column) = match error.line_col {
+            pest::error::LineColLocation::Pos(pos) => pos,
+            pest::error::LineColLocation::Span(start, _) => start,
+        };
+        let message = error.variant.message().into_owned();
+
+        Self::new(ErrorCode::Syntax, message.clone()).with_label(
+            Span {
+                file: file.clone(),
+                start,
+                end,
+                line,
+                column,
+            },
+            message,
+        )
+    }
+
+    /// Renders the diagnostic in the style of `rustc`, quoting the labelled lines of `source`.
+    ///
+    /// `source` must be the contents of the file named by the labels' spans.
+    pub fn render(&self, source: &str) -> String {
+        let mut out = format!("error[{}]: {}\n", self.code, self.message);
+        let mut labels = self.labels.iter().collect::<Vec<_>>();
+        labels.sort_by_key(|label| (label.span.line, label.span.column));
+
+        let gutter = labels
+            .iter()
+            .map(|label| label.span.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let pad = " ".repeat(gutter);
+
+        if let Some(span) = self.primary_span().or(labels.first().map(|l| &l.span)) {
+            out.push_str(&format!("{}--> {}\n", pad, span));
+            out.push_str(&format!("{} |\n", pad));
+        }
+
+        for label in &labels {
+            let text = source.lines().nth(label.span.line - 1).unwrap_or("");
+            let available = text.chars().count().saturating_sub(label.span.column - 1);
+            let width = source
+                .get(label.span.start..label.span.end)
+                .map(|s| s.lines().next().unwrap_or("").chars().count())
+                .unwrap_or(0)
+                .clamp(1, available.max(1));
+            let marker = if label.primary { "^" } else { "-" };
+
+            out.push_str(&format!(
+                "{:>gutter$} | {}\n",
+                label.span.line,
+                text,
+                gutter = gutter
+            ));
+            out.push_str(&format!(
+                "{} | {}{} {}\n",
+                pad,
+                " ".repeat(label.span.column - 1),
+                marker.repeat(width),
+                label.message
+            ));
+        }
+
+        if !self.notes.is_empty() {
+            out.push_str(&format!("{} |\n", pad));
+        }
+
+        for note in &self.notes {
+            out.push_str(&format!("{} = note: {}\n", pad, note));
+        }
+
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error[{}]: {}", self.code, self.message)?;
+
+        if let Some(span) = self.primary_span() {
+            write!(f, " at {}", span)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
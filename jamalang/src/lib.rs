@@ -1,4 +1,6 @@
+pub mod analysis;
 pub mod compiler;
+pub mod diagnostic;
 pub mod parser;
 #[cfg(test)]
 mod tests;
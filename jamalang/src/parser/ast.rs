@@ -1,10 +1,14 @@
-use std::fs;
+use std::{collections::HashMap, fmt, sync::Arc};
 
 use enum_variant_type::EnumVariantType;
+use pest::iterators::Pair;
 
-use crate::parser::{
-    expr::{parse_expression, Expr as CExpr},
-    parse_ast, JamalangFile, Rule,
+use crate::{
+    diagnostic::{Diagnostic, ErrorCode, Span},
+    parser::{
+        expr::{parse_expression, Expr as CExpr, Op},
+        JamalangFile, Rule,
+    },
 };
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -15,41 +19,125 @@ pub enum AssignmentType {
     Mul,
     Div,
     Pow,
+    BitAnd,
+    BitOr,
+    ShiftLeft,
+    ShiftRight,
+}
+
+impl AssignmentType {
+    /// The operator that combines the current value with the assigned one, or `None` for `=`.
+    pub fn operator(self) -> Option<Op> {
+        match self {
+            AssignmentType::Set => None,
+            AssignmentType::Add => Some(Op::Add),
+            AssignmentType::Sub => Some(Op::Subtract),
+            AssignmentType::Mul => Some(Op::Multiply),
+            AssignmentType::Div => Some(Op::Divide),
+            AssignmentType::Pow => Some(Op::Power),
+            AssignmentType::BitAnd => Some(Op::BitAnd),
+            AssignmentType::BitOr => Some(Op::BitOr),
+            AssignmentType::ShiftLeft => Some(Op::ShiftLeft),
+            AssignmentType::ShiftRight => Some(Op::ShiftRight),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     Void,
-    Number,
+    Float,
+    Double,
+    UnsignedInt,
+    SignedInt,
+    Long,
     Char,
     Bool,
-    Array { len: u32, kind: Box<Type> },
+    String,
+    Array {
+        len: u32,
+        kind: Box<Type>,
+    },
+    /// An anonymous record, with its fields sorted by name.
+    Record(Vec<(String, Type)>),
     Custom(String),
 }
 
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Void => f.write_str("void"),
+            Type::Float => f.write_str("Float"),
+            Type::Double => f.write_str("Double"),
+            Type::UnsignedInt => f.write_str("UnsignedInt"),
+            Type::SignedInt => f.write_str("SignedInt"),
+            Type::Long => f.write_str("Long"),
+            Type::Char => f.write_str("char"),
+            Type::Bool => f.write_str("bool"),
+            Type::String => f.write_str("string"),
+            Type::Array { .. } => {
+                let mut lengths = Vec::new();
+                let mut kind = self;
+
+                while let Type::Array { len, kind: inner } = kind {
+                    lengths.push(len);
+                    kind = inner;
+                }
+
+                write!(f, "{}", kind)?;
+                lengths
+                    .into_iter()
+                    .try_for_each(|len| write!(f, "[{}]", len))
+            }
+            Type::Record(fields) => {
+                f.write_str("{ ")?;
+
+                for (index, (name, kind)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    write!(f, "{}: {}", name, kind)?;
+                }
+
+                f.write_str(" }")
+            }
+            Type::Custom(name) => f.write_str(name),
+        }
+    }
+}
+
 #[derive(Debug, Clone, EnumVariantType)]
 pub enum Statement {
     #[evt(derive(Clone, Debug))]
     Assignment {
         identifier: String,
-        index: Option<CExpr>,
+        /// The type the variable is declared with, which is only allowed for `=`.
+        type_hint: Option<Type>,
+        /// The indices into the array being assigned to, outermost first.
+        indices: Vec<CExpr>,
         r#type: AssignmentType,
         value: CExpr,
+        span: Span,
     },
     #[evt(derive(Clone, Debug))]
     FunctionDefinition {
         lambda: bool,
         identifier: String,
         parameters: Vec<(String, Type)>,
-        return_type_hint: Type,
+        /// The return type as written. Functions without one have it inferred from their `return`s
+        /// by [`crate::analysis::check`], which fills it in before any code is generated.
+        return_type_hint: Option<Type>,
         body: Vec<Statement>,
+        span: Span,
     },
     #[evt(derive(Clone, Debug))]
-    Return { value: CExpr },
+    Return { value: CExpr, span: Span },
     #[evt(derive(Clone, Debug))]
     WhileLoop {
         condition: CExpr,
         body: Vec<Statement>,
+        span: Span,
     },
     #[evt(derive(Clone, Debug))]
     ForLoop {
@@ -57,6 +145,7 @@ pub enum Statement {
         identifier_type_hint: Option<Type>,
         expression: CExpr,
         body: Vec<Statement>,
+        span: Span,
     },
     #[evt(derive(Clone, Debug))]
     IfStatement {
@@ -64,33 +153,67 @@ pub enum Statement {
         body: Vec<Statement>,
         else_ifs: Vec<(CExpr, Vec<Statement>)>,
         else_body: Vec<Statement>,
+        span: Span,
     },
+    /// An `import`. The parser only records the path as written, the `ImportResolver` then fills
+    /// in `file` for the first import of each file and leaves it empty for any later ones.
     #[evt(derive(Clone, Debug))]
-    ForeignModule(JamalangFile),
+    ForeignModule {
+        path: String,
+        file: Option<JamalangFile>,
+        span: Span,
+    },
     #[evt(derive(Clone, Debug))]
-    Expr { expr: CExpr },
+    Expr { expr: CExpr, span: Span },
     #[evt(derive(Clone, Debug))]
-    Comment(String),
+    Comment(String, Span),
 }
 
-pub(crate) fn parse_statement<'a>(pair: pest::iterators::Pair<'a, Rule>) -> Statement {
-    let statement = pair
-        .into_inner()
-        .next()
-        .expect("statement pair should have at least one inner pair");
-    match statement.as_rule() {
+impl Statement {
+    pub fn span(&self) -> &Span {
+        match self {
+            Statement::Assignment { span, .. }
+            | Statement::FunctionDefinition { span, .. }
+            | Statement::Return { span, .. }
+            | Statement::WhileLoop { span, .. }
+            | Statement::ForLoop { span, .. }
+            | Statement::IfStatement { span, .. }
+            | Statement::ForeignModule { span, .. }
+            | Statement::Expr { span, .. }
+            | Statement::Comment(_, span) => span,
+        }
+    }
+}
+
+pub(crate) fn parse_statement<'a>(
+    file: &Arc<str>,
+    pair: Pair<'a, Rule>,
+) -> Result<Statement, Diagnostic> {
+    let span = Span::new(file, pair.as_span());
+    let statement = pair.into_inner().next().ok_or_else(|| {
+        Diagnostic::internal(
+            span.clone(),
+            "statement pair should have at least one inner pair",
+        )
+    })?;
+    let span = Span::new(file, statement.as_span());
+    let statement = match statement.as_rule() {
         Rule::Assignment => {
             let mut identifier = None;
-            let mut index = None;
+            let mut type_hint = None;
+            let mut indices = Vec::new();
             let mut r#type = None;
             let mut expr = None;
 
             for inner in statement.into_inner() {
                 match inner.as_rule() {
                     Rule::Identifier => identifier = Some(inner.as_str().to_owned()),
-                    Rule::ArrayIndexing => index = Some(parse_expression(inner.into_inner())),
+                    Rule::TypeHint => type_hint = Some(parse_type_hint(file, inner)?),
+                    Rule::ArrayIndexing => {
+                        indices.push(parse_expression(file, inner.into_inner())?)
+                    }
                     Rule::Expr => {
-                        expr = Some(parse_expression(inner.into_inner()));
+                        expr = Some(parse_expression(file, inner.into_inner())?);
                     }
                     _ => {
                         r#type = Some(match inner.as_rule() {
@@ -100,17 +223,29 @@ pub(crate) fn parse_statement<'a>(pair: pest::iterators::Pair<'a, Rule>) -> Stat
                             Rule::AssMul => AssignmentType::Mul,
                             Rule::AssDiv => AssignmentType::Div,
                             Rule::AssPow => AssignmentType::Pow,
-                            _ => unreachable!("Unexpected rule {:#?}", inner.as_rule()),
+                            Rule::AssBitAnd => AssignmentType::BitAnd,
+                            Rule::AssBitOr => AssignmentType::BitOr,
+                            Rule::AssShl => AssignmentType::ShiftLeft,
+                            Rule::AssShr => AssignmentType::ShiftRight,
+                            rule => return Err(unexpected_rule(file, &inner, rule)),
                         });
                     }
                 }
             }
 
             Statement::Assignment {
-                identifier: identifier.expect("assignment requires an identifier"),
-                index,
-                r#type: r#type.expect("assignment requires a type"),
-                value: expr.expect("assignment requires a value"),
+                identifier: identifier.ok_or_else(|| {
+                    Diagnostic::internal(span.clone(), "assignment requires an identifier")
+                })?,
+                type_hint,
+                indices,
+                r#type: r#type.ok_or_else(|| {
+                    Diagnostic::internal(span.clone(), "assignment requires a type")
+                })?,
+                value: expr.ok_or_else(|| {
+                    Diagnostic::internal(span.clone(), "assignment requires a value")
+                })?,
+                span,
             }
         }
         Rule::FunctionDefinition => {
@@ -124,42 +259,51 @@ pub(crate) fn parse_statement<'a>(pair: pest::iterators::Pair<'a, Rule>) -> Stat
                 match inner_pair.as_rule() {
                     Rule::Lambda => lambda = true,
                     Rule::IdentifierDefinition => identifier = Some(inner_pair.as_str().to_owned()),
-                    Rule::TypeHint => return_type_hint = Some(parse_type_hint(inner_pair.as_str())),
+                    Rule::TypeHint => return_type_hint = Some(parse_type_hint(file, inner_pair)?),
                     Rule::FunctionArgsDefinitionList => {
-                        let mut parameter = (None, None);
+                        let mut arg_pairs = inner_pair.into_inner().peekable();
 
-                        for arg_pair in inner_pair.into_inner() {
-                            match arg_pair.as_rule() {
-                                Rule::IdentifierDefinition => {
-                                    parameter.0 = Some(arg_pair.as_str());
-                                }
-                                Rule::TypeHint => {
-                                    parameter.1 = Some(parse_type_hint(arg_pair.as_str()))
-                                }
-                                _ => unreachable!("Unexpected rule {:#?}", arg_pair.as_rule()),
+                        while let Some(name) = arg_pairs.next() {
+                            if name.as_rule() != Rule::IdentifierDefinition {
+                                return Err(unexpected_rule(file, &name, name.as_rule()));
                             }
 
-                            if parameter.0.is_some() && parameter.1.is_some() {
-                                parameters
-                                    .push((parameter.0.unwrap().to_owned(), parameter.1.unwrap()));
-                                parameter.0 = None;
-                                parameter.1 = None;
-                            }
+                            // The grammar allows parameters without types so that they can be
+                            // reported here, since they aren't inferred.
+                            let Some(hint) =
+                                arg_pairs.next_if(|pair| pair.as_rule() == Rule::TypeHint)
+                            else {
+                                return Err(Diagnostic::new(
+                                    ErrorCode::MissingTypeHint,
+                                    format!("parameter `{}` has no type", name.as_str()),
+                                )
+                                .with_label(
+                                    Span::new(file, name.as_span()),
+                                    format!("add a type, such as `{}: SignedInt`", name.as_str()),
+                                )
+                                .with_note("the types of parameters aren't inferred"));
+                            };
+
+                            parameters
+                                .push((name.as_str().to_owned(), parse_type_hint(file, hint)?));
                         }
                     }
                     Rule::Block => {
-                        body.extend(inner_pair.into_inner().map(parse_statement));
+                        body.extend(parse_block(file, inner_pair)?);
                     }
-                    _ => unreachable!("Unexpected rule {:#?}", inner_pair.as_rule()),
+                    rule => return Err(unexpected_rule(file, &inner_pair, rule)),
                 }
             }
 
             Statement::FunctionDefinition {
                 lambda,
-                return_type_hint: return_type_hint.unwrap_or(Type::Void),
-                identifier: identifier.expect("function definition requires an identifier"),
+                return_type_hint,
+                identifier: identifier.ok_or_else(|| {
+                    Diagnostic::internal(span.clone(), "function definition requires an identifier")
+                })?,
                 parameters,
                 body,
+                span,
             }
         }
         Rule::IfBlock => {
@@ -176,16 +320,18 @@ pub(crate) fn parse_statement<'a>(pair: pest::iterators::Pair<'a, Rule>) -> Stat
                         for if_inner_pair in if_inner {
                             match if_inner_pair.as_rule() {
                                 Rule::Expr => {
-                                    condition = Some(parse_expression(if_inner_pair.into_inner()));
+                                    condition =
+                                        Some(parse_expression(file, if_inner_pair.into_inner())?);
                                 }
                                 Rule::Block => {
-                                    body.extend(if_inner_pair.into_inner().map(parse_statement));
+                                    body.extend(parse_block(file, if_inner_pair)?);
                                 }
-                                _ => unreachable!("Unexpected rule {:#?}", if_inner_pair.as_rule()),
+                                rule => return Err(unexpected_rule(file, &if_inner_pair, rule)),
                             }
                         }
                     }
                     Rule::ElifStatement => {
+                        let elif_span = Span::new(file, inner_pair.as_span());
                         let elif_inner = inner_pair.into_inner();
                         let mut elif_condition = None;
                         let mut elif_body = Vec::new();
@@ -194,20 +340,19 @@ pub(crate) fn parse_statement<'a>(pair: pest::iterators::Pair<'a, Rule>) -> Stat
                             match elif_inner_pair.as_rule() {
                                 Rule::Expr => {
                                     elif_condition =
-                                        Some(parse_expression(elif_inner_pair.into_inner()));
+                                        Some(parse_expression(file, elif_inner_pair.into_inner())?);
                                 }
                                 Rule::Block => {
-                                    elif_body
-                                        .extend(elif_inner_pair.into_inner().map(parse_statement));
-                                }
-                                _ => {
-                                    unreachable!("Unexpected rule {:#?}", elif_inner_pair.as_rule())
+                                    elif_body.extend(parse_block(file, elif_inner_pair)?);
                                 }
+                                rule => return Err(unexpected_rule(file, &elif_inner_pair, rule)),
                             }
                         }
 
                         else_ifs.push((
-                            elif_condition.expect("elif requires a condition"),
+                            elif_condition.ok_or_else(|| {
+                                Diagnostic::internal(elif_span, "elif requires a condition")
+                            })?,
                             elif_body,
                         ));
                     }
@@ -217,24 +362,24 @@ pub(crate) fn parse_statement<'a>(pair: pest::iterators::Pair<'a, Rule>) -> Stat
                         for else_inner_pair in else_inner {
                             match else_inner_pair.as_rule() {
                                 Rule::Block => {
-                                    else_body
-                                        .extend(else_inner_pair.into_inner().map(parse_statement));
-                                }
-                                _ => {
-                                    unreachable!("Unexpected rule {:#?}", else_inner_pair.as_rule())
+                                    else_body.extend(parse_block(file, else_inner_pair)?);
                                 }
+                                rule => return Err(unexpected_rule(file, &else_inner_pair, rule)),
                             }
                         }
                     }
-                    _ => unreachable!("Unexpected rule {:#?}", inner_pair.as_rule()),
+                    rule => return Err(unexpected_rule(file, &inner_pair, rule)),
                 }
             }
 
             Statement::IfStatement {
-                condition: condition.expect("if statement requires a condition"),
+                condition: condition.ok_or_else(|| {
+                    Diagnostic::internal(span.clone(), "if statement requires a condition")
+                })?,
                 body,
                 else_ifs,
                 else_body,
+                span,
             }
         }
         Rule::WhileStatement => {
@@ -244,18 +389,21 @@ pub(crate) fn parse_statement<'a>(pair: pest::iterators::Pair<'a, Rule>) -> Stat
             for inner_pair in statement.into_inner() {
                 match inner_pair.as_rule() {
                     Rule::Expr => {
-                        condition = Some(parse_expression(inner_pair.into_inner()));
+                        condition = Some(parse_expression(file, inner_pair.into_inner())?);
                     }
                     Rule::Block => {
-                        body.extend(inner_pair.into_inner().map(parse_statement));
+                        body.extend(parse_block(file, inner_pair)?);
                     }
-                    _ => unreachable!("Unexpected rule {:#?}", inner_pair.as_rule()),
+                    rule => return Err(unexpected_rule(file, &inner_pair, rule)),
                 }
             }
 
             Statement::WhileLoop {
-                condition: condition.expect("while statement requires a condition"),
+                condition: condition.ok_or_else(|| {
+                    Diagnostic::internal(span.clone(), "while statement requires a condition")
+                })?,
                 body,
+                span,
             }
         }
         Rule::ForStatement => {
@@ -267,69 +415,172 @@ pub(crate) fn parse_statement<'a>(pair: pest::iterators::Pair<'a, Rule>) -> Stat
             for inner_pair in statement.into_inner() {
                 match inner_pair.as_rule() {
                     Rule::Identifier => identifier = Some(inner_pair.as_str()),
-                    Rule::TypeHint => type_hint = Some(parse_type_hint(inner_pair.as_str())),
+                    Rule::TypeHint => type_hint = Some(parse_type_hint(file, inner_pair)?),
                     Rule::Expr => {
-                        expr = Some(parse_expression(inner_pair.into_inner()));
+                        expr = Some(parse_expression(file, inner_pair.into_inner())?);
                     }
                     Rule::Block => {
-                        body.extend(inner_pair.into_inner().map(parse_statement));
+                        body.extend(parse_block(file, inner_pair)?);
                     }
-                    _ => unreachable!("Unexpected rule {:#?}", inner_pair.as_rule()),
+                    rule => return Err(unexpected_rule(file, &inner_pair, rule)),
                 }
             }
 
             Statement::ForLoop {
                 identifier: identifier
-                    .expect("for statement requires an identifier")
+                    .ok_or_else(|| {
+                        Diagnostic::internal(span.clone(), "for statement requires an identifier")
+                    })?
                     .to_owned(),
                 identifier_type_hint: type_hint,
-                expression: expr.expect("for statement requires a value"),
+                expression: expr.ok_or_else(|| {
+                    Diagnostic::internal(span.clone(), "for statement requires a value")
+                })?,
                 body,
+                span,
             }
         }
         Rule::ImportStatement => {
-            //TODO: Make paths relative to the file they are in
             let path = statement
                 .into_inner()
                 .next()
-                .expect("import requires a path")
+                .ok_or_else(|| Diagnostic::internal(span.clone(), "import requires a path"))?
                 .as_str()
+                .trim()
                 .to_owned();
-            let file = fs::read_to_string(&path).expect("imported file should be available");
-            Statement::ForeignModule(
-                parse_ast(&path, &file).expect("imported file should have valid syntax"),
-            )
+
+            Statement::ForeignModule {
+                path,
+                file: None,
+                span,
+            }
         }
         Rule::Expr => Statement::Expr {
-            expr: parse_expression(statement.into_inner()),
+            expr: parse_expression(file, statement.into_inner())?,
+            span,
         },
         Rule::ReturnStatement => Statement::Return {
-            value: parse_expression(statement.into_inner()),
+            value: parse_expression(file, statement.into_inner())?,
+            span,
         },
-        Rule::Comment => Statement::Comment(statement.as_str().to_owned()),
-        _ => unreachable!("Unexpected rule {:#?}", statement.as_rule()),
-    }
+        Rule::Comment => Statement::Comment(statement.as_str().to_owned(), span),
+        rule => return Err(unexpected_rule(file, &statement, rule)),
+    };
+
+    Ok(statement)
 }
 
-fn parse_type_hint(mut hint: &str) -> Type {
-    if hint.starts_with(':') {
-        //TODO: Proper fix
-        hint = hint.trim_start_matches(": ");
-    }
+pub(crate) fn parse_block(file: &Arc<str>, pair: Pair<Rule>) -> Result<Vec<Statement>, Diagnostic> {
+    pair.into_inner()
+        .map(|pair| parse_statement(file, pair))
+        .collect()
+}
+
+pub(crate) fn unexpected_rule(file: &Arc<str>, pair: &Pair<Rule>, rule: Rule) -> Diagnostic {
+    Diagnostic::internal(
+        Span::new(file, pair.as_span()),
+        format!("Unexpected rule {:#?}", rule),
+    )
+}
+
+fn parse_type_hint(file: &Arc<str>, pair: Pair<Rule>) -> Result<Type, Diagnostic> {
+    let span = Span::new(file, pair.as_span());
+    let r#type = pair
+        .into_inner()
+        .next()
+        .ok_or_else(|| Diagnostic::internal(span, "type hint should contain a type"))?;
+
+    parse_type(file, r#type)
+}
 
-    match hint {
-        "number" => Type::Number,
-        "bool" => Type::Bool,
-        "void" => Type::Void,
-        "char" => Type::Char,
-        _ => {
-            if hint.ends_with("]") {
-                todo!()
-                // let len = hint.split_at(mi)
-                // Type::Array(Box::new(parse_type_hint(hint.trim_end_matches("]"))))
-            } else {
-                Type::Custom(hint.to_owned())
+pub(crate) fn parse_type(file: &Arc<str>, pair: Pair<Rule>) -> Result<Type, Diagnostic> {
+    let span = Span::new(file, pair.as_span());
+    let mut inner = pair.into_inner();
+    let base = inner
+        .next()
+        .ok_or_else(|| Diagnostic::internal(span, "type should have a name"))?;
+
+    let r#type = match base.as_rule() {
+        Rule::Identifier => match base.as_str() {
+            "number" | "Float" => Type::Float,
+            "Double" => Type::Double,
+            "UnsignedInt" => Type::UnsignedInt,
+            "SignedInt" => Type::SignedInt,
+            "Long" => Type::Long,
+            "bool" => Type::Bool,
+            "void" | "Void" => Type::Void,
+            "char" => Type::Char,
+            "string" | "String" => Type::String,
+            hint => Type::Custom(hint.to_owned()),
+        },
+        Rule::RecordType => {
+            let mut fields = Vec::new();
+
+            for field in base.into_inner() {
+                let field_span = Span::new(file, field.as_span());
+                let mut field_inner = field.into_inner();
+                let (Some(name), Some(hint)) = (field_inner.next(), field_inner.next()) else {
+                    return Err(Diagnostic::internal(
+                        field_span,
+                        "record field should have a name and a type",
+                    ));
+                };
+
+                fields.push((
+                    name.as_str().to_owned(),
+                    Span::new(file, name.as_span()),
+                    parse_type_hint(file, hint)?,
+                ));
             }
+
+            check_duplicate_fields(fields.iter().map(|(name, span, _)| (name.as_str(), span)))?;
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+
+            Type::Record(
+                fields
+                    .into_iter()
+                    .map(|(name, _, r#type)| (name, r#type))
+                    .collect(),
+            )
+        }
+        rule => return Err(unexpected_rule(file, &base, rule)),
+    };
+
+    // `number[2][3]` is an array of 2 arrays of 3 numbers, so the last length is the innermost.
+    let lengths = inner
+        .map(|length| {
+            length.as_str().parse::<u32>().map_err(|_| {
+                Diagnostic::new(ErrorCode::Syntax, "array length is too large")
+                    .with_label(Span::new(file, length.as_span()), "array length")
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(lengths
+        .into_iter()
+        .rev()
+        .fold(r#type, |kind, len| Type::Array {
+            len,
+            kind: Box::new(kind),
+        }))
+}
+
+/// Rejects records which name the same field twice.
+pub(crate) fn check_duplicate_fields<'a>(
+    fields: impl IntoIterator<Item = (&'a str, &'a Span)>,
+) -> Result<(), Diagnostic> {
+    let mut seen: HashMap<&str, &Span> = HashMap::new();
+
+    for (name, span) in fields {
+        if let Some(first) = seen.insert(name, span) {
+            return Err(Diagnostic::new(
+                ErrorCode::DuplicateField,
+                format!("field `{}` is defined more than once", name),
+            )
+            .with_label(span.clone(), "defined again here")
+            .with_secondary_label(first.clone(), "first defined here"));
         }
     }
+
+    Ok(())
 }
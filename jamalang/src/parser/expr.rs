@@ -1,42 +1,103 @@
-use crate::parser::{
-    ast::{parse_statement, Statement},
-    Rule,
+use std::{collections::HashSet, fmt, sync::Arc};
+
+use crate::{
+    diagnostic::{Diagnostic, ErrorCode, Span},
+    parser::{
+        ast::{check_duplicate_fields, parse_block, parse_type, unexpected_rule, Statement, Type},
+        Rule,
+    },
 };
 use enum_variant_type::EnumVariantType;
 use pest::pratt_parser::PrattParser;
 
 #[derive(Debug, Clone, EnumVariantType)]
 pub enum Expr {
-    #[evt(derive(Clone, Copy, Debug, PartialEq))]
-    Float(f32),
-    #[evt(derive(Clone, Copy, Debug, PartialEq))]
-    Bool(bool),
-    #[evt(derive(Clone, Copy, Debug, PartialEq))]
-    Char(char),
-    #[evt(derive(Clone, Copy, Debug, PartialEq))]
-    TypeNone,
+    /// A float literal, with the type it is used as once analysis has filled it in.
+    #[evt(derive(Clone, Debug, PartialEq))]
+    Float(f64, Option<Type>, Span),
+    #[evt(derive(Clone, Debug, PartialEq))]
+    /// An integer literal, with the type given by its suffix, such as `UnsignedInt` for `10u`.
+    /// Analysis fills in the type it is used as for literals without one.
+    Integer(i64, Option<Type>, Span),
+    #[evt(derive(Clone, Debug, PartialEq))]
+    Bool(bool, Span),
+    #[evt(derive(Clone, Debug, PartialEq))]
+    Char(char, Span),
+    #[evt(derive(Clone, Debug, PartialEq))]
+    Str(String, Span),
+    /// A string literal containing `{expr}`, made up of the literal text between each expression
+    /// and the expressions themselves.
+    #[evt(derive(Clone, Debug))]
+    Interpolation(Vec<Expr>, Span),
     #[evt(derive(Clone, Debug, PartialEq))]
-    Identifier(String),
+    TypeNone(Span),
+    #[evt(derive(Clone, Debug, PartialEq))]
+    Identifier(String, Span),
     #[evt(derive(Clone, Debug))]
     FunctionCall {
         identifier: String,
         parameters: Vec<Expr>,
         lambda_body: Option<Vec<Statement>>,
+        span: Span,
     },
     #[evt(derive(Clone, Debug))]
-    UnaryMinus(Box<Expr>),
+    UnaryMinus(Box<Expr>, Span),
+    #[evt(derive(Clone, Debug))]
+    Not(Box<Expr>, Span),
+    /// Flips every bit of an integer, written `~x`.
+    #[evt(derive(Clone, Debug))]
+    BitNot(Box<Expr>, Span),
+    #[evt(derive(Clone, Debug))]
+    Array(Vec<Expr>, Span),
+    /// A record literal, with its fields in the order they were written.
     #[evt(derive(Clone, Debug))]
-    Array(Vec<Expr>),
+    Object(Vec<(String, Expr)>, Span),
     #[evt(derive(Clone, Debug))]
-    ArrayIndex { array: Box<Expr>, index: Box<Expr> },
+    ArrayIndex {
+        array: Box<Expr>,
+        index: Box<Expr>,
+        span: Span,
+    },
+    /// Converts a value to another type, such as `x as Long`.
+    #[evt(derive(Clone, Debug))]
+    Cast {
+        value: Box<Expr>,
+        r#type: Type,
+        span: Span,
+    },
     #[evt(derive(Clone, Debug))]
     BinOp {
         lhs: Box<Expr>,
         op: Op,
         rhs: Box<Expr>,
+        span: Span,
     },
 }
 
+impl Expr {
+    pub fn span(&self) -> &Span {
+        match self {
+            Expr::Float(_, _, span)
+            | Expr::Integer(_, _, span)
+            | Expr::Bool(_, span)
+            | Expr::Char(_, span)
+            | Expr::Str(_, span)
+            | Expr::Interpolation(_, span)
+            | Expr::TypeNone(span)
+            | Expr::Identifier(_, span)
+            | Expr::FunctionCall { span, .. }
+            | Expr::UnaryMinus(_, span)
+            | Expr::Not(_, span)
+            | Expr::BitNot(_, span)
+            | Expr::Array(_, span)
+            | Expr::Object(_, span)
+            | Expr::ArrayIndex { span, .. }
+            | Expr::Cast { span, .. }
+            | Expr::BinOp { span, .. } => span,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Op {
     Add,
@@ -52,6 +113,50 @@ pub enum Op {
     Gte,
     Eq,
     NotEq,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+impl Op {
+    /// Whether the operator compares its operands, giving a `bool`.
+    pub fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            Op::Lt | Op::Gt | Op::Lte | Op::Gte | Op::Eq | Op::NotEq
+        )
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Op::Add => "+",
+            Op::Subtract => "-",
+            Op::Multiply => "*",
+            Op::Divide => "/",
+            Op::Modulo => "%",
+            Op::Power => "**",
+            Op::Range => "..",
+            Op::Lt => "<",
+            Op::Gt => ">",
+            Op::Lte => "<=",
+            Op::Gte => ">=",
+            Op::Eq => "is",
+            Op::NotEq => "is not",
+            Op::And => "and",
+            Op::Or => "or",
+            Op::BitAnd => "&",
+            Op::BitOr => "|",
+            Op::BitXor => "^",
+            Op::ShiftLeft => "<<",
+            Op::ShiftRight => ">>",
+        })
+    }
 }
 
 lazy_static::lazy_static! {
@@ -59,76 +164,153 @@ lazy_static::lazy_static! {
         use pest::pratt_parser::{Assoc::*, Op};
         use crate::parser::Rule::*;
 
+        // From the loosest binding to the tightest.
         PrattParser::new()
-            .op(Op::infix(Add, Left) | Op::infix(Sub, Left))
+            .op(Op::infix(Or, Left))
+            .op(Op::infix(And, Left))
+            .op(Op::prefix(Not))
             .op(Op::infix(Lt, Left) | Op::infix(Gt, Left) | Op::infix(Lte, Left) | Op::infix(Gte, Left) | Op::infix(Eq, Left) | Op::infix(NotEq, Left))
-            .op(Op::infix(Mul, Left) | Op::infix(Div, Left) | Op::infix(Mod, Left) | Op::infix(Pow, Left) | Op::infix(Range, Left))
-            .op(Op::prefix(UnaryMinus))
+            .op(Op::infix(Range, Left))
+            .op(Op::infix(BitOr, Left))
+            .op(Op::infix(BitXor, Left))
+            .op(Op::infix(BitAnd, Left))
+            .op(Op::infix(Shl, Left) | Op::infix(Shr, Left))
+            .op(Op::infix(Add, Left) | Op::infix(Sub, Left))
+            .op(Op::infix(Mul, Left) | Op::infix(Div, Left) | Op::infix(Mod, Left))
+            .op(Op::postfix(Cast))
+            .op(Op::prefix(UnaryMinus) | Op::prefix(BitNot))
+            .op(Op::infix(Pow, Right))
             .op(Op::postfix(ArrayIndexing))
     };
 }
 
-pub(crate) fn parse_expression<'a>(pairs: pest::iterators::Pairs<'a, Rule>) -> Expr {
+pub(crate) fn parse_expression<'a>(
+    file: &Arc<str>,
+    pairs: pest::iterators::Pairs<'a, Rule>,
+) -> Result<Expr, Diagnostic> {
+    let pairs = pairs.collect::<Vec<_>>();
+    // The literals that a minus applies to on their own, as a `**` or indexing after one binds
+    // more tightly than the minus does.
+    let negated = (1..pairs.len())
+        .filter(|&index| {
+            pairs[index - 1].as_rule() == Rule::UnaryMinus
+                && pairs[index].as_rule() == Rule::Integer
+                && !matches!(
+                    pairs.get(index + 1).map(|pair| pair.as_rule()),
+                    Some(Rule::Pow | Rule::ArrayIndexing)
+                )
+        })
+        .map(|index| pairs[index].as_span().start())
+        .collect::<HashSet<_>>();
+
     PRATT_PARSER
-        .map_primary(|primary| match primary.as_rule() {
-            Rule::Float => Expr::Float(
-                primary
-                    .as_str()
-                    .parse::<f32>()
-                    .expect("value should be a valid number"),
-            ),
-            Rule::Bool => Expr::Bool(primary.as_str() == "true"),
-            Rule::Char => Expr::Char(
-                primary
-                    .as_str()
-                    .chars()
-                    .nth(1)
-                    .expect("character should be present"),
-            ),
-            Rule::Identifier => Expr::Identifier(primary.as_str().to_owned()),
-            Rule::FunctionCall => {
-                let mut parameters = Vec::new();
-                let mut identifier = None;
-                let mut lambda_body = None;
-
-                for inner_pair in primary.into_inner() {
-                    match inner_pair.as_rule() {
-                        Rule::Identifier => {
-                            identifier = Some(inner_pair.as_str().to_owned());
-                        }
-                        Rule::FunctionArgsList => {
-                            parameters.extend(
-                                inner_pair
-                                    .into_inner()
-                                    .map(|pair| parse_expression(pair.into_inner())),
-                            );
+        .map_primary(|primary| {
+            let span = Span::new(file, primary.as_span());
+
+            match primary.as_rule() {
+                Rule::Float => parse_float(primary.as_str(), span),
+                Rule::Integer => {
+                    let negated = negated.contains(&span.start);
+                    parse_integer(primary.as_str(), negated, span)
+                }
+                Rule::Bool => Ok(Expr::Bool(primary.as_str() == "true", span)),
+                Rule::Char => match primary.as_str().chars().nth(1) {
+                    Some(value) => Ok(Expr::Char(value, span)),
+                    None => Err(Diagnostic::internal(span, "character should be present")),
+                },
+                Rule::String => {
+                    let mut parts = Vec::new();
+
+                    for part in primary.into_inner() {
+                        match part.as_rule() {
+                            Rule::DoubleQuotedText | Rule::SingleQuotedText => parts.push(
+                                Expr::Str(unescape(part.as_str()), Span::new(file, part.as_span())),
+                            ),
+                            Rule::StringInterpolation => {
+                                parts.push(parse_expression(file, part.into_inner())?)
+                            }
+                            rule => return Err(unexpected_rule(file, &part, rule)),
                         }
-                        Rule::Block => {
-                            lambda_body =
-                                Some(inner_pair.into_inner().map(parse_statement).collect());
+                    }
+
+                    match parts.as_slice() {
+                        [] => Ok(Expr::Str(String::new(), span)),
+                        [Expr::Str(value, _)] => Ok(Expr::Str(value.clone(), span)),
+                        _ => Ok(Expr::Interpolation(parts, span)),
+                    }
+                }
+                Rule::Identifier => Ok(Expr::Identifier(primary.as_str().to_owned(), span)),
+                Rule::FunctionCall => {
+                    let mut parameters = Vec::new();
+                    let mut identifier = None;
+                    let mut lambda_body = None;
+
+                    for inner_pair in primary.into_inner() {
+                        match inner_pair.as_rule() {
+                            Rule::Identifier => {
+                                identifier = Some(inner_pair.as_str().to_owned());
+                            }
+                            Rule::FunctionArgsList => {
+                                for pair in inner_pair.into_inner() {
+                                    parameters.push(parse_expression(file, pair.into_inner())?);
+                                }
+                            }
+                            Rule::Block => {
+                                lambda_body = Some(parse_block(file, inner_pair)?);
+                            }
+                            rule => return Err(unexpected_rule(file, &inner_pair, rule)),
                         }
-                        _ => unreachable!("Unexpected rule {:#?}", inner_pair.as_rule()),
                     }
+
+                    Ok(Expr::FunctionCall {
+                        identifier: identifier.ok_or_else(|| {
+                            Diagnostic::internal(
+                                span.clone(),
+                                "function identifier should be present",
+                            )
+                        })?,
+                        parameters,
+                        lambda_body,
+                        span,
+                    })
                 }
+                Rule::Array => {
+                    let mut elements = Vec::new();
 
-                Expr::FunctionCall {
-                    identifier: identifier.expect("function identifier should be present"),
-                    parameters,
-                    lambda_body,
+                    for inner_pair in primary.into_inner() {
+                        elements.push(parse_expression(file, inner_pair.into_inner())?);
+                    }
+
+                    Ok(Expr::Array(elements, span))
                 }
-            }
-            Rule::Array => {
-                let mut elements = Vec::new();
+                Rule::None => Ok(Expr::TypeNone(span)),
+                Rule::Expr => parse_expression(file, primary.into_inner()),
+                Rule::Object => {
+                    let mut fields = Vec::new();
+                    let mut inner = primary.into_inner();
+
+                    while let (Some(name), Some(value)) = (inner.next(), inner.next()) {
+                        fields.push((
+                            name.as_str().to_owned(),
+                            Span::new(file, name.as_span()),
+                            parse_expression(file, value.into_inner())?,
+                        ));
+                    }
 
-                primary.into_inner().for_each(|inner_pair| {
-                    elements.push(parse_expression(inner_pair.into_inner()));
-                });
+                    check_duplicate_fields(
+                        fields.iter().map(|(name, span, _)| (name.as_str(), span)),
+                    )?;
 
-                Expr::Array(elements)
+                    Ok(Expr::Object(
+                        fields
+                            .into_iter()
+                            .map(|(name, _, value)| (name, value))
+                            .collect(),
+                        span,
+                    ))
+                }
+                rule => Err(unexpected_rule(file, &primary, rule)),
             }
-            Rule::None => Expr::TypeNone,
-            Rule::Expr => parse_expression(primary.into_inner()),
-            rule => unreachable!("parse_expression expected atom, found {:?}", rule),
         })
         .map_infix(|lhs, op, rhs| {
             let op = match op.as_rule() {
@@ -145,24 +327,148 @@ pub(crate) fn parse_expression<'a>(pairs: pest::iterators::Pairs<'a, Rule>) -> E
                 Rule::Gte => Op::Gte,
                 Rule::NotEq => Op::NotEq,
                 Rule::Eq => Op::Eq,
-                rule => unreachable!("Expr::parse expected infix operation, found {:?}", rule),
+                Rule::And => Op::And,
+                Rule::Or => Op::Or,
+                Rule::BitAnd => Op::BitAnd,
+                Rule::BitOr => Op::BitOr,
+                Rule::BitXor => Op::BitXor,
+                Rule::Shl => Op::ShiftLeft,
+                Rule::Shr => Op::ShiftRight,
+                rule => return Err(unexpected_rule(file, &op, rule)),
             };
-            Expr::BinOp {
+            let (lhs, rhs) = (lhs?, rhs?);
+            Ok(Expr::BinOp {
+                span: lhs.span().to(rhs.span()),
                 lhs: Box::new(lhs),
                 op,
                 rhs: Box::new(rhs),
-            }
+            })
         })
         .map_prefix(|op, rhs| match op.as_rule() {
-            Rule::UnaryMinus => Expr::UnaryMinus(Box::new(rhs)),
-            _ => unreachable!(),
+            Rule::UnaryMinus => {
+                let rhs = rhs?;
+                let span = Span::new(file, op.as_span()).to(rhs.span());
+                Ok(Expr::UnaryMinus(Box::new(rhs), span))
+            }
+            Rule::Not => {
+                let rhs = rhs?;
+                let span = Span::new(file, op.as_span()).to(rhs.span());
+                Ok(Expr::Not(Box::new(rhs), span))
+            }
+            Rule::BitNot => {
+                let rhs = rhs?;
+                let span = Span::new(file, op.as_span()).to(rhs.span());
+                Ok(Expr::BitNot(Box::new(rhs), span))
+            }
+            rule => Err(unexpected_rule(file, &op, rule)),
         })
         .map_postfix(|lhs, op| match op.as_rule() {
-            Rule::ArrayIndexing => Expr::ArrayIndex {
-                array: Box::new(lhs),
-                index: Box::new(parse_expression(op.into_inner())),
-            },
-            _ => unreachable!(),
+            Rule::ArrayIndexing => {
+                let lhs = lhs?;
+                Ok(Expr::ArrayIndex {
+                    span: lhs.span().to(&Span::new(file, op.as_span())),
+                    array: Box::new(lhs),
+                    index: Box::new(parse_expression(file, op.into_inner())?),
+                })
+            }
+            Rule::Cast => {
+                let lhs = lhs?;
+                let span = lhs.span().to(&Span::new(file, op.as_span()));
+                let r#type = op
+                    .into_inner()
+                    .find(|pair| pair.as_rule() == Rule::Type)
+                    .ok_or_else(|| Diagnostic::internal(span.clone(), "cast should have a type"))?;
+                Ok(Expr::Cast {
+                    value: Box::new(lhs),
+                    r#type: parse_type(file, r#type)?,
+                    span,
+                })
+            }
+            rule => Err(unexpected_rule(file, &op, rule)),
         })
-        .parse(pairs)
+        .parse(pairs.into_iter())
+}
+
+fn parse_float(text: &str, span: Span) -> Result<Expr, Diagnostic> {
+    let value = text
+        .replace('_', "")
+        .parse::<f64>()
+        .map_err(|_| Diagnostic::internal(span.clone(), "value should be a valid number"))?;
+
+    if value.is_infinite() {
+        return Err(Diagnostic::new(
+            ErrorCode::LiteralOverflow,
+            "float literal doesn't fit in `Double`",
+        )
+        .with_label(span, format!("the largest `Double` is {:e}", f64::MAX)));
+    }
+
+    Ok(Expr::Float(value, None, span))
+}
+
+/// Parses an integer literal, which may be written in hexadecimal (`0xFF`), binary (`0b1010`) or
+/// octal (`0o755`), and may end with `u` for an `UnsignedInt` or `L` for a `Long`.
+///
+/// A literal with a minus in front of it may be one more than the largest `Long`, so that
+/// `-9223372036854775808` can be written. Its value wraps around to `i64::MIN`, which negating
+/// leaves as it is.
+fn parse_integer(text: &str, negated: bool, span: Span) -> Result<Expr, Diagnostic> {
+    let digits = text.replace('_', "");
+    let long_max = i64::MAX as u64 + negated as u64;
+    let (digits, suffix, max) = if let Some(digits) = digits.strip_suffix('u') {
+        (digits, Some(Type::UnsignedInt), u32::MAX as u64)
+    } else if let Some(digits) = digits.strip_suffix('L') {
+        (digits, Some(Type::Long), long_max)
+    } else {
+        (digits.as_str(), None, long_max)
+    };
+    let (digits, radix) = match digits.get(..2) {
+        Some("0x") => (&digits[2..], 16),
+        Some("0b") => (&digits[2..], 2),
+        Some("0o") => (&digits[2..], 8),
+        _ => (digits, 10),
+    };
+
+    match u64::from_str_radix(digits, radix) {
+        Ok(value) if value <= max => Ok(Expr::Integer(value as i64, suffix, span)),
+        _ => {
+            let r#type = suffix.unwrap_or(Type::Long);
+            let label = if negated && r#type == Type::Long {
+                format!("the smallest `Long` is {}", i64::MIN)
+            } else {
+                format!("the largest `{}` is {}", r#type, max)
+            };
+            Err(Diagnostic::new(
+                ErrorCode::LiteralOverflow,
+                format!("integer literal doesn't fit in `{}`", r#type),
+            )
+            .with_label(span, label))
+        }
+    }
+}
+
+/// Replaces the escape sequences in the text of a string literal.
+fn unescape(text: &str) -> String {
+    let mut chars = text.chars();
+    let mut string = String::new();
+
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            string.push(char);
+            continue;
+        }
+
+        string.push(match chars.next() {
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            // The grammar only allows escaping quotes, slashes, braces and the characters above.
+            Some(char) => char,
+            None => unreachable!("string literals should not end with a backslash"),
+        });
+    }
+
+    string
 }
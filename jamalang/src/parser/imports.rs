@@ -0,0 +1,220 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    diagnostic::{Diagnostic, ErrorCode, Span},
+    parser::{ast::Statement, parse_ast_with, JamalangFile},
+};
+
+/// Finds and parses the files named by `import` statements.
+///
+/// Paths are looked up relative to the importing file first, and then in each of the search
+/// paths in order. Every file is only parsed once; later imports of it are left without a body.
+/// Two different files with the same name can't both be imported, as their names would clash.
+#[derive(Debug, Default)]
+pub struct ImportResolver {
+    pub search_paths: Vec<PathBuf>,
+    loaded: HashSet<PathBuf>,
+    /// The file imported under each namespace so far.
+    namespaces: HashMap<String, String>,
+    /// The files that are currently being parsed, outermost first.
+    stack: Vec<(PathBuf, String)>,
+}
+
+impl ImportResolver {
+    /// Creates a resolver which searches `lib_dirs`, followed by the directories in `JAMALANG_PATH`.
+    pub fn new(lib_dirs: Vec<PathBuf>) -> Self {
+        let mut search_paths = lib_dirs;
+
+        if let Some(paths) = env::var_os("JAMALANG_PATH") {
+            search_paths.extend(env::split_paths(&paths));
+        }
+
+        Self {
+            search_paths,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn resolve(&mut self, file: &mut JamalangFile) -> Vec<Diagnostic> {
+        let key = canonicalize(Path::new(&file.source_file));
+        let dir = Path::new(&file.source_file)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let mut diagnostics = Vec::new();
+
+        self.loaded.insert(key.clone());
+        self.stack.push((key, file.source_file.clone()));
+        self.resolve_statements(&dir, &mut file.statements, &mut diagnostics);
+        self.stack.pop();
+
+        diagnostics
+    }
+
+    fn resolve_statements(
+        &mut self,
+        dir: &Path,
+        statements: &mut [Statement],
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        for statement in statements {
+            match statement {
+                Statement::ForeignModule { path, file, span } => match self.load(dir, path, span) {
+                    Ok((resolved, loaded)) => {
+                        *path = resolved;
+                        *file = loaded;
+                    }
+                    Err(mut errors) => diagnostics.append(&mut errors),
+                },
+                Statement::FunctionDefinition { body, .. }
+                | Statement::WhileLoop { body, .. }
+                | Statement::ForLoop { body, .. } => {
+                    self.resolve_statements(dir, body, diagnostics)
+                }
+                Statement::IfStatement {
+                    body,
+                    else_ifs,
+                    else_body,
+                    ..
+                } => {
+                    self.resolve_statements(dir, body, diagnostics);
+
+                    for (_, body) in else_ifs {
+                        self.resolve_statements(dir, body, diagnostics);
+                    }
+
+                    self.resolve_statements(dir, else_body, diagnostics);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Returns the canonical path of the import, and its contents if it has not been loaded yet.
+    fn load(
+        &mut self,
+        dir: &Path,
+        path: &str,
+        span: &Span,
+    ) -> Result<(String, Option<JamalangFile>), Vec<Diagnostic>> {
+        let candidates = if Path::new(path).is_absolute() {
+            vec![PathBuf::from(path)]
+        } else {
+            std::iter::once(dir)
+                .chain(self.search_paths.iter().map(PathBuf::as_path))
+                .map(|dir| dir.join(path))
+                .collect()
+        };
+
+        let Some(found) = candidates.iter().find(|candidate| candidate.is_file()) else {
+            let mut diagnostic = Diagnostic::new(
+                ErrorCode::ImportNotFound,
+                format!("could not find imported file `{}`", path),
+            )
+            .with_label(span.clone(), "imported here");
+
+            for candidate in &candidates {
+                diagnostic = diagnostic.with_note(format!("looked in `{}`", candidate.display()));
+            }
+
+            return Err(vec![diagnostic]);
+        };
+
+        let key = canonicalize(found);
+        let resolved = key.to_string_lossy().into_owned();
+
+        if let Some(start) = self.stack.iter().position(|(loading, _)| *loading == key) {
+            let cycle = self.stack[start..]
+                .iter()
+                .map(|(_, name)| format!("`{}`", name))
+                .chain(std::iter::once(format!("`{}`", self.stack[start].1)))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+
+            return Err(vec![Diagnostic::new(
+                ErrorCode::ImportCycle,
+                format!("`{}` is imported by a file it imports", path),
+            )
+            .with_label(span.clone(), "this import creates a cycle")
+            .with_note(format!("the import cycle is {}", cycle))]);
+        }
+
+        if self.loaded.contains(&key) {
+            return Ok((resolved, None));
+        }
+
+        let name = found.to_string_lossy().into_owned();
+        let namespace = namespace(&resolved);
+
+        // The names are used as `namespace.name`, so the namespace has to be an identifier.
+        if !is_identifier(&namespace) {
+            return Err(vec![Diagnostic::new(
+                ErrorCode::InvalidNamespace,
+                format!(
+                    "`{}` can't be used as the name of an imported file",
+                    namespace
+                ),
+            )
+            .with_label(span.clone(), format!("`{}` is imported here", name))
+            .with_note("an imported file's names are prefixed with the file's name")
+            .with_note(
+                "rename the file so that its name starts with a letter and only contains letters, \
+                 digits and underscores",
+            )]);
+        }
+
+        if let Some(first) = self.namespaces.get(&namespace) {
+            return Err(vec![Diagnostic::new(
+                ErrorCode::NamespaceCollision,
+                format!("another file named `{}` is already imported", namespace),
+            )
+            .with_label(span.clone(), format!("`{}` is imported here", name))
+            .with_note(format!(
+                "`{}` is already imported as `{}`",
+                first, namespace
+            ))
+            .with_note(
+                "rename one of the files so that they have different names",
+            )]);
+        }
+
+        self.namespaces.insert(namespace, name.clone());
+        let source = fs::read_to_string(found).map_err(|error| {
+            vec![Diagnostic::new(
+                ErrorCode::ImportNotFound,
+                format!("could not read imported file `{}`", name),
+            )
+            .with_label(span.clone(), error.to_string())]
+        })?;
+
+        parse_ast_with(&name, &source, self).map(|file| (resolved, Some(file)))
+    }
+}
+
+/// Returns the namespace an imported file's names are prefixed with, which is its file name without
+/// its extension, given its resolved path.
+pub(crate) fn namespace(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Whether `name` matches the grammar's rule for a single identifier.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .map_or(false, |first| first.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Falls back to the path as given for files that don't exist, such as in-memory test sources.
+fn canonicalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
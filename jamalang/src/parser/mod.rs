@@ -1,8 +1,14 @@
+use std::sync::Arc;
+
 use ast::{parse_statement, Statement};
 use pest::Parser;
 
+use crate::diagnostic::{Diagnostic, Span};
+use imports::ImportResolver;
+
 pub mod ast;
 pub mod expr;
+pub mod imports;
 
 #[derive(pest_derive::Parser)]
 #[grammar = "./parser/grammar.pest"]
@@ -14,30 +20,64 @@ pub struct JamalangFile {
     pub statements: Vec<Statement>,
 }
 
-pub fn parse_ast<'a>(
+/// Parses a file and everything it imports, searching only the directories in `JAMALANG_PATH`.
+pub fn parse_ast(source_file: &str, input: &str) -> Result<JamalangFile, Vec<Diagnostic>> {
+    parse_ast_with(source_file, input, &mut ImportResolver::new(Vec::new()))
+}
+
+/// Parses a file and everything it imports, returning every statement-level error rather than
+/// stopping at the first one.
+pub fn parse_ast_with(
     source_file: &str,
-    input: &'a str,
-) -> Result<JamalangFile, pest::error::Error<Rule>> {
-    let pairs = JamalangParser::parse(Rule::File, input)?;
+    input: &str,
+    resolver: &mut ImportResolver,
+) -> Result<JamalangFile, Vec<Diagnostic>> {
+    let file: Arc<str> = Arc::from(source_file);
+    let pairs = JamalangParser::parse(Rule::File, input)
+        .map_err(|error| vec![Diagnostic::from_pest(&file, error)])?;
     let mut statements = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    let file_pair = pairs.into_iter().next().ok_or_else(|| {
+        vec![Diagnostic::internal(
+            Span {
+                file: file.clone(),
+                start: 0,
+                end: 0,
+                line: 1,
+                column: 1,
+            },
+            "file should contain at least one statement",
+        )]
+    })?;
 
-    for pair in pairs
-        .into_iter()
-        .next()
-        .expect("file should contain at last one statement")
-        .into_inner()
-    {
+    for pair in file_pair.into_inner() {
         match pair.as_rule() {
-            Rule::Statement => {
-                statements.push(parse_statement(pair));
-            }
+            Rule::Statement => match parse_statement(&file, pair) {
+                Ok(statement) => statements.push(statement),
+                Err(diagnostic) => diagnostics.push(diagnostic),
+            },
             Rule::EOI => (),
-            _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
+            rule => diagnostics.push(Diagnostic::internal(
+                Span::new(&file, pair.as_span()),
+                format!("Unexpected rule: {:?}", rule),
+            )),
         }
     }
 
-    Ok(JamalangFile {
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+
+    let mut file = JamalangFile {
         source_file: source_file.to_string(),
         statements,
-    })
+    };
+    let diagnostics = resolver.resolve(&mut file);
+
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+
+    Ok(file)
 }
@@ -1,3 +1,6 @@
+mod parser;
+mod precedence;
+
 macro_rules! assert_outputs {
     ($($input:expr => $expected:expr),*) => {
         $(
@@ -9,22 +12,22 @@ macro_rules! assert_outputs {
             let context = inkwell::context::Context::create();
             let module = context.create_module("main");
             let builder = context.create_builder();
-        
+
             let mut compiler = crate::compiler::Compiler::new(&context, &module, &builder);
-        
-            compiler.compile(script);
+
+            compiler.compile(script).expect("script should compile");
 
             let execution_engine = compiler
                 .module
                 .create_jit_execution_engine(inkwell::OptimizationLevel::Aggressive)
                 .expect("module should be valid");
-        
+
             unsafe {
                 type Main = unsafe extern "C" fn();
                 let main: inkwell::execution_engine::JitFunction<Main> = execution_engine
                     .get_function(&format!("{}_entry", "test_file"))
                     .expect("entry function should be defined");
-        
+
                 main.call();
             }
 
@@ -35,9 +38,679 @@ macro_rules! assert_outputs {
     };
 }
 
+/// Compiles a script that should fail to compile, returning the errors it was rejected with.
+fn compile_errors(source: &str) -> Vec<crate::diagnostic::Diagnostic> {
+    let script = crate::parser::parse_ast("test_file", source).expect("syntax should be valid");
+    let context = inkwell::context::Context::create();
+    let module = context.create_module("main");
+    let builder = context.create_builder();
+    let mut compiler = crate::compiler::Compiler::new(&context, &module, &builder);
+
+    match compiler.compile(script) {
+        Ok(_) => panic!("script should not compile"),
+        Err(diagnostics) => diagnostics,
+    }
+}
+
+/// Compiles a script, returning the LLVM IR generated for it.
+fn compiled_ir(source: &str) -> String {
+    compiled_ir_with(source, |_| ())
+}
+
+/// Compiles a script with a compiler that `configure` has set up, returning the LLVM IR generated
+/// for it.
+fn compiled_ir_with(
+    source: &str,
+    configure: impl FnOnce(&mut crate::compiler::Compiler<'_, '_>),
+) -> String {
+    let script = crate::parser::parse_ast("test_file", source).expect("syntax should be valid");
+    let context = inkwell::context::Context::create();
+    let module = context.create_module("main");
+    let builder = context.create_builder();
+    let mut compiler = crate::compiler::Compiler::new(&context, &module, &builder);
+    configure(&mut compiler);
+    compiler.compile(script).expect("script should compile");
+
+    module.print_to_string().to_string()
+}
+
 #[test]
 fn print_number() {
     assert_outputs!(
         "println(1)" => "1\n"
     );
-}
\ No newline at end of file
+}
+
+#[test]
+fn imports_are_namespaced_and_initialized_once() {
+    let dir = parser::write_files(
+        "imports_namespaced",
+        &[
+            (
+                "math.jamalang",
+                "println(1)\nscale: SignedInt = 2\nfunction double(x: SignedInt): SignedInt {\n    return x * scale\n}\n",
+            ),
+            (
+                "other.jamalang",
+                "import math.jamalang\nfunction quadruple(x: SignedInt): SignedInt {\n    return math.double(math.double(x))\n}\n",
+            ),
+        ],
+    );
+    let script = format!(
+        "import {0}/math.jamalang\nimport {0}/other.jamalang\nprintln(math.double(3))\nprintln(other.quadruple(1))\nprintln(math.scale)",
+        dir.display()
+    );
+
+    assert_outputs!(
+        &script => "1\n6\n4\n2\n"
+    );
+}
+
+#[test]
+fn imported_functions_are_declared_before_any_bodies() {
+    let dir = parser::write_files(
+        "imports_declared_early",
+        &[(
+            "shapes.jamalang",
+            "function area(w: SignedInt, h: SignedInt): SignedInt {\n    return w * h\n}\n",
+        )],
+    );
+    let script = format!(
+        "function square(n: SignedInt): SignedInt {{\n    return shapes.area(n, n)\n}}\nimport {0}/shapes.jamalang\nprintln(square(3))",
+        dir.display()
+    );
+
+    assert_outputs!(
+        &script => "9\n"
+    );
+}
+
+#[test]
+fn top_level_imports_run_before_the_rest_of_the_file() {
+    let dir = parser::write_files(
+        "imports_run_first",
+        &[(
+            "shapes.jamalang",
+            "println(\"shapes\")\nscale: SignedInt = 2\nfunction area(w: SignedInt, h: SignedInt): SignedInt {\n    return w * h * scale\n}\n",
+        )],
+    );
+    let script = format!(
+        "println(\"main\")\nprintln(shapes.area(2, 3))\nprintln(shapes.scale)\nimport {0}/shapes.jamalang",
+        dir.display()
+    );
+
+    assert_outputs!(
+        &script => "shapes\nmain\n12\n2\n"
+    );
+}
+
+#[test]
+fn strings() {
+    assert_outputs!(
+        "println(\"Hello, world!\")" => "Hello, world!\n",
+        "println('tab\\there')" => "tab\there\n",
+        "greeting = \"Hello \" + \"there\"\nprintln(greeting + \"!\")" => "Hello there!\n",
+        "println(\"a\" is \"a\")\nprintln(\"a\" is not \"a\")\nprintln(\"a\" + \"b\" is \"ab\")" => "true\nfalse\ntrue\n",
+        "function greet(name: string): string {\n    return \"Hi \" + name\n}\nprintln(greet(\"Bob\"))" => "Hi Bob\n",
+        "function greet(name: String): String {\n    return \"Hi \" + name\n}\ns: String = greet(\"Ann\")\nprintln(s)" => "Hi Ann\n"
+    );
+}
+
+#[test]
+fn chains_of_string_joins_allocate_once() {
+    let calls = |source, builtin| compiled_ir(source).matches(builtin).count();
+    let concat = "call i8* @\"jamalang_internal$string_concat\"";
+    let free = "call void @\"jamalang_internal$string_free\"";
+
+    assert_eq!(
+        calls("a = \"a\"\nprintln(a + \"b\" + a + \"c\")", concat),
+        1
+    );
+    assert_eq!(calls("a = \"a\"\nprintln(a + (a + \"b\"))", free), 1);
+    assert_eq!(calls("s = \"a\"\ns += s + \"b\"", free), 1);
+    assert_eq!(calls("s = \"a\"\nt = s + \"b\"\ns += t", free), 0);
+    assert_eq!(calls("x = 1\nprintln(\"{x} and {[x, 2]}!\")", concat), 1);
+    assert_eq!(calls("x = 1\nprintln(\"{x} and {[x, 2]}!\")", free), 3);
+
+    assert_outputs!(
+        "a = \"a\"\nb = \"b\"\nprintln(a + (b + a) + \"\" + b)" => "abab\n",
+        "s = \"\"\nfor i in 0..3 {\n    s += \"{i},\" + s\n}\nprintln(s)" => "0,1,0,2,0,1,0,\n"
+    );
+}
+
+#[test]
+fn string_interpolation() {
+    assert_outputs!(
+        "x = 2\nprintln(\"x is {x}, doubled is {x * 2}\")" => "x is 2, doubled is 4\n",
+        "println(\"{true} {'c'} {[1, 2, 3]} {\"nested\"}\")" => "true c [1, 2, 3] nested\n",
+        "println(\"\\{not interpolated}\")" => "{not interpolated}\n"
+    );
+}
+
+#[test]
+fn records() {
+    assert_outputs!(
+        "p = { x: 1, y: 2 }\nprintln(p.x + p.y)" => "3\n",
+        "p = { x: 1, y: 2 }\np.y = 5\nprintln(\"{p}\")" => "{x: 1, y: 5}\n",
+        "line = { start: { x: 0, y: 0 }, end: { x: 3, y: 4 } }\nline.end.x = 6\nprintln(line.end.x)" => "6\n",
+        "function flip(p: { x: SignedInt, y: SignedInt }): { x: SignedInt, y: SignedInt } {\n    return { y: p.x, x: p.y }\n}\nq = flip({ x: 1, y: 2 })\nprintln(\"{q}\")" => "{x: 2, y: 1}\n"
+    );
+}
+
+#[test]
+fn array_type_hints() {
+    assert_outputs!(
+        "xs: SignedInt[3] = [1, 2, 3]\nprintln(\"{xs}\")" => "[1, 2, 3]\n",
+        "function second(xs: SignedInt[3]): SignedInt {\n    return xs[1]\n}\nprintln(second([4, 5, 6]))" => "5\n",
+        "for c: char in ['a', 'b'] {\n    println(c)\n}" => "a\nb\n"
+    );
+}
+
+#[test]
+fn arrays_in_functions() {
+    assert_outputs!(
+        "function sum(xs: SignedInt[3]): SignedInt {\n    total: SignedInt = 0\n    for x in xs {\n        total += x\n    }\n    return total\n}\nprintln(sum([1, 2, 3]))" => "6\n",
+        "function reverse(xs: SignedInt[3]): SignedInt[3] {\n    return [xs[2], xs[1], xs[0]]\n}\nprintln(\"{reverse([1, 2, 3])}\")" => "[3, 2, 1]\n",
+        "function clear(xs: UnsignedInt[2]) {\n    xs[0] = 0\n}\nys = [1, 2]\nclear(ys)\nprintln(\"{ys}\")" => "[1, 2]\n"
+    );
+}
+
+#[test]
+fn runtime_array_literals() {
+    assert_outputs!(
+        "x = 2\nxs = [x, x + 1, x * 2]\nprintln(\"{xs}\")" => "[2, 3, 4]\n",
+        "function f(): SignedInt {\n    return 7\n}\nprintln(\"{[f(), 1]}\")" => "[7, 1]\n",
+        "grid = [[1, 2], [3, 4]]\nprintln(\"{grid}\")\nprintln(grid[1][0])" => "[[1, 2], [3, 4]]\n3\n",
+        "x = 5\ngrid: UnsignedInt[2][2] = [[x, 0], [0, x]]\nprintln(\"{grid}\")" => "[[5, 0], [0, 5]]\n",
+        "names = [\"a\", \"b\"]\nprintln(\"{names}\")" => "[a, b]\n",
+        "i = 0\ntotal = 0\nwhile i < 1000000 {\n    pair = [i, i + 1]\n    total += pair[1] - pair[0]\n    i += 1\n}\nprintln(total)" => "1000000\n"
+    );
+}
+
+#[test]
+fn runtime_indexing() {
+    assert_outputs!(
+        "xs = [1, 2, 3]\ni = 0\nwhile i < 3 {\n    println(xs[i])\n    i += 1\n}" => "1\n2\n3\n",
+        "xs = [1, 2, 3]\ni = 2\nxs[i] = 9\nprintln(\"{xs}\")" => "[1, 2, 9]\n"
+    );
+}
+
+#[test]
+fn indexing_in_a_loop_doesnt_grow_the_stack() {
+    assert_outputs!(
+        "xs = 0..1000\ntotal = 0\ni = 0\nwhile i < 100000 {\n    total += xs[i % 1000]\n    i += 1\n}\nprintln(total)" => "49950000\n",
+        "grid = [0..100, 0..100]\ntotal = 0\ni = 0\nwhile i < 100000 {\n    total += grid[i % 2][i % 100]\n    i += 1\n}\nprintln(total)" => "4950000\n",
+        "function sum(xs: UnsignedInt[100], times: UnsignedInt): UnsignedInt {\n    total = 0\n    i = 0\n    while i < times {\n        x = xs[i % 100]\n        total += x\n        i += 1\n    }\n    return total\n}\nprintln(sum(0..100, 100000))" => "4950000\n"
+    );
+}
+
+#[test]
+fn array_indices_must_be_integers() {
+    let diagnostics = compile_errors("xs = [1, 2]\nprintln(xs[0.5])\nprintln(xs[true])");
+
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>(),
+        ["cannot index with `Double`", "cannot index with `bool`"]
+    );
+}
+
+#[test]
+fn bounds_checks_can_be_disabled() {
+    let ir = |bounds_checks| {
+        compiled_ir_with("xs = [1, 2]\ni = 1\nprintln(xs[i])", |compiler| {
+            compiler.bounds_checks = bounds_checks;
+        })
+    };
+
+    assert!(ir(true).contains("call void @\"jamalang_internal$index_out_of_bounds\""));
+    assert!(!ir(false).contains("call void @\"jamalang_internal$index_out_of_bounds\""));
+}
+
+#[test]
+fn compound_assignment_on_elements_and_fields() {
+    assert_outputs!(
+        "xs = [1, 2, 3]\ni = 0\nwhile i < 3 {\n    xs[i] += 10\n    i += 1\n}\nprintln(\"{xs}\")" => "[11, 12, 13]\n",
+        "grid = [[1, 2], [3, 4]]\ngrid[1][0] *= 2\ngrid[0][1] -= 2\nprintln(\"{grid}\")" => "[[1, 0], [6, 4]]\n",
+        "p = { age: 30 }\np.age -= 1\np.age /= 2\nprintln(p.age)" => "14\n",
+        "words = [\"a\", \"b\"]\nwords[1] += \"c\"\nprintln(words[1])" => "bc\n"
+    );
+}
+
+#[test]
+fn power() {
+    assert_outputs!(
+        "println(2 ** 10)" => "1024\n",
+        "println(2 ** 3 ** 2)" => "512\n",
+        "println(-2 ** 2)" => "-4\n",
+        "println(2.0 ** -1.0)" => "0.5\n",
+        "x: SignedInt = 2\ny = -1\nprintln(x ** y)\nprintln(-1 ** y)\nprintln(1 ** y)" => "0\n-1\n1\n",
+        "x = 2\ny = 0.5\nprintln(16.0 ** y)\nprintln(x ** x)" => "4\n4\n",
+        "x = 9.0\nx **= 0.5\nprintln(x)\nx ^= 2.0\nprintln(x)" => "3\n9\n"
+    );
+}
+
+#[test]
+fn logical_operators() {
+    assert_outputs!(
+        "println(true and false)\nprintln(true or false)\nprintln(not true)" => "false\ntrue\nfalse\n",
+        "x = 5\nprintln(x > 1 and x < 10)\nprintln(not x is 5 or x is 5)" => "true\ntrue\n",
+        "function loud(): bool {\n    println(\"evaluated\")\n    return true\n}\nprintln(false and loud())\nprintln(true or loud())\nprintln(true and loud())" => "false\ntrue\nevaluated\ntrue\n",
+        "a = true\nb = false\nprintln(a and (b or a and not b))" => "true\n"
+    );
+}
+
+#[test]
+fn equality_and_ordering() {
+    assert_outputs!(
+        "println(true is true)\nprintln(true is not false)" => "true\ntrue\n",
+        "println('a' is 'a')\nprintln('a' < 'b')\nprintln('b' <= 'a')\nprintln('z' >= 'z')" => "true\ntrue\nfalse\ntrue\n",
+        "xs = [1, 2, 3]\nprintln(xs is [1, 2, 3])\nprintln(xs is not [1, 2, 4])" => "true\ntrue\n",
+        "println([[1, 2], [3, 4]] is [[1, 2], [3, 5]])\nprintln([\"a\", \"b\"] is [\"a\", \"b\"])" => "false\ntrue\n"
+    );
+}
+
+#[test]
+fn comparing_different_types_is_an_error() {
+    let diagnostics = compile_errors("println(1 is true)\nprintln('a' < \"b\")\nprintln(1 + 'a')");
+
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == crate::diagnostic::ErrorCode::TypeMismatch));
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "cannot compare `UnsignedInt` with `bool`",
+            "cannot compare `char` with `string`",
+            "cannot use `+` on `UnsignedInt` and `char`",
+        ]
+    );
+}
+
+#[test]
+fn integers() {
+    assert_outputs!(
+        "println(7 / 2)\nprintln(-7 / 2)\nprintln(-7 % 3)" => "3\n-3\n-1\n",
+        "big: SignedInt = 2147483647\nprintln(big + 1)" => "-2147483648\n",
+        "x: Long = 3000000000\nprintln(x * 2)\nprintln(3000000000)" => "6000000000\n3000000000\n",
+        "u: UnsignedInt = 4000000000\nprintln(u)\nprintln(u / 3)\nprintln(u > 1)" => "4000000000\n1333333333\ntrue\n",
+        "u: UnsignedInt = 0\nu -= 1\nprintln(\"{u}\")" => "4294967295\n",
+        "println(3 ** 4)\nprintln(2 ** -1)\nn: Long = 2\nprintln(n ** 40)" => "81\n0\n1099511627776\n",
+        "function half(n: Long): Long {\n    return n / 2\n}\nprintln(half(5))\nprintln(\"{half(9)}\")" => "2\n4\n",
+        "x = -9223372036854775808\nprintln(x)\nd: Double = -9223372036854775808\nprintln(d < 0.0)" => "-9223372036854775808\ntrue\n"
+    );
+}
+
+#[test]
+fn integer_division_by_zero_is_checked() {
+    let check = "call void @\"jamalang_internal$division_by_zero\"";
+
+    assert!(compiled_ir("x = 7\ny = 0\nprintln(x / y)").contains(check));
+    assert!(compiled_ir("u: UnsignedInt = 7\nu %= u").contains(check));
+    assert!(compiled_ir("x: Long = 7\nprintln(x / 0)").contains(check));
+    assert!(!compiled_ir("x: SignedInt = 7\nprintln(x / 2)\nprintln(x % -1)").contains(check));
+    assert!(compiled_ir("x: SignedInt = 0\ny = -1\nprintln(x ** y)").contains(check));
+    assert!(
+        !compiled_ir("x = 0\nprintln(x ** 2)\nu: UnsignedInt = 0\nprintln(u ** u)").contains(check)
+    );
+
+    assert_outputs!(
+        "x = -2147483648\ny = -1\nprintln(x / y)\nprintln(x % y)\nprintln(7 / y)" => "-2147483648\n0\n-7\n"
+    );
+}
+
+#[test]
+fn literal_expressions_take_the_type_they_are_used_as() {
+    assert_outputs!(
+        "x: Long = 2147483647 + 1\nprintln(x)" => "2147483648\n",
+        "d: Double = 1 / 2\nprintln(d)\nf: Float = -(3 * 0.5)\nprintln(f)" => "0.5\n-1.5\n",
+        "u: UnsignedInt = ~0\nprintln(u)" => "4294967295\n",
+        "x = 4000000000\nprintln(x)\ny = -1\nprintln(y + 2)\nprintln(-(1 + 2))" => "4000000000\n1\n-3\n"
+    );
+}
+
+#[test]
+fn mixing_integer_types_is_an_error() {
+    let diagnostics =
+        compile_errors("x: Long = 1\ny = 2\nprintln(x + y)\nu: UnsignedInt = 1\nprintln(-u)");
+
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "cannot use `+` on `Long` and `UnsignedInt`",
+            "cannot negate `UnsignedInt`",
+        ]
+    );
+}
+
+#[test]
+fn floats_and_casts() {
+    assert_outputs!(
+        "x: Double = 0.1\nprintln(x)\ny: Float = 0.1\nprintln(y)\nprintln(0.1 + 0.2)" => "0.1\n0.1\n0.30000000000000004\n",
+        "f: Float = 1.5\nd = 2.25\nprintln(f + d)\nprintln(d * 2)" => "3.75\n4.5\n",
+        "println(7 as Double / 2)\nprintln(3.99 as SignedInt)\nprintln(-3.99 as SignedInt)" => "3.5\n3\n-3\n",
+        "big = 10000000000.0\nprintln(big as SignedInt)\nprintln(-1 as UnsignedInt)" => "2147483647\n4294967295\n",
+        "println(65 as char)\nprintln('a' as SignedInt)" => "A\n97\n",
+        "println(0 as bool)\nprintln(2.5 as bool)\nprintln(true as Long)" => "false\ntrue\n1\n",
+        "u: UnsignedInt = 4000000000\nprintln(u as Long)\nprintln(u as Double)" => "4000000000\n4000000000\n"
+    );
+}
+
+#[test]
+fn mixing_numbers_needs_a_cast() {
+    let diagnostics = compile_errors(
+        "x = 1\ny = 2.5\nprintln(x + y)\nprintln(\"1\" as SignedInt)\nprintln(\"a\" - \"b\")\nz: Float = 1.5\nprintln(z * x)",
+    );
+
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.message.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (
+                crate::diagnostic::ErrorCode::TypeMismatch,
+                "cannot use `+` on `UnsignedInt` and `Double`"
+            ),
+            (
+                crate::diagnostic::ErrorCode::InvalidCast,
+                "cannot cast `string` to `SignedInt`"
+            ),
+            (
+                crate::diagnostic::ErrorCode::TypeMismatch,
+                "cannot use `-` on `string`"
+            ),
+            (
+                crate::diagnostic::ErrorCode::TypeMismatch,
+                "cannot use `*` on `Float` and `UnsignedInt`"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn literals_in_other_bases() {
+    assert_outputs!(
+        "println(0xFF)\nprintln(0b1010)\nprintln(0o755)" => "255\n10\n493\n",
+        "println(1.5e-3)\nprintln(2e3)" => "0.0015\n2000\n",
+        "println(4000000000u)\nprintln(10L * 1000000000)" => "4000000000\n10000000000\n",
+        "mask: UnsignedInt = 0xFFFF_FFFF\nprintln(mask)" => "4294967295\n"
+    );
+}
+
+#[test]
+fn constants_that_dont_fit_are_errors() {
+    let diagnostics = compile_errors(
+        "x: SignedInt = 3000000000\nu: UnsignedInt = -1\nprintln(u + -2)\nfunction f(n: SignedInt) {}\nf(0x1_0000_0000)\ny: Float = 1e39",
+    );
+
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == crate::diagnostic::ErrorCode::LiteralOverflow));
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "`3000000000` doesn't fit in `SignedInt`",
+            "`-1` doesn't fit in `UnsignedInt`",
+            "`-2` doesn't fit in `UnsignedInt`",
+            "`4294967296` doesn't fit in `SignedInt`",
+            "`1e39` doesn't fit in `Float`",
+        ]
+    );
+}
+
+#[test]
+fn bitwise_operators() {
+    assert_outputs!(
+        "println(12 & 10)\nprintln(12 | 10)\nprintln(12 ^ 10)\nprintln(~5)" => "8\n14\n6\n4294967290\n",
+        "x = -16\nprintln(1 << 4)\nprintln(x >> 2)\nu: UnsignedInt = 4294967280\nprintln(u >> 2)" => "16\n-4\n1073741820\n",
+        "x = 6\nx &= 3\nprintln(x)\nx |= 8\nprintln(x)\nx <<= 2\nprintln(x)\nx >>= 1\nprintln(x)" => "2\n10\n40\n20\n",
+        "println(1 + 2 << 1)\nprintln(6 & 3 is 2)" => "6\ntrue\n"
+    );
+}
+
+#[test]
+fn bitwise_operators_need_integers() {
+    let diagnostics =
+        compile_errors("println(1.5 & 2.0)\nprintln(~true)\nx = 1\nprintln(x << 2.0)");
+
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "cannot use `&` on `Double`",
+            "cannot use `~` on `bool`",
+            "cannot use `<<` on `UnsignedInt` and `Double`",
+        ]
+    );
+}
+
+#[test]
+fn every_type_error_is_reported_before_compiling() {
+    let diagnostics =
+        compile_errors("function f() {\n    x = 1 + true\n}\nwhile 1 {\n}\nprintln(~1.5)");
+
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "cannot use `+` on `UnsignedInt` and `bool`",
+            "expected `bool`, found `UnsignedInt`",
+            "cannot use `~` on `Double`",
+        ]
+    );
+}
+
+#[test]
+fn unknown_types_are_reported() {
+    for (source, name) in [
+        ("x: Foo = 1", "Foo"),
+        ("function f(a: Bar[2]) {}", "Bar"),
+        ("function f(): { x: Baz } {\n    return { x: 1 }\n}", "Baz"),
+        ("println(1 as Qux)", "Qux"),
+        ("for x: Quux in [1, 2] {\n    println(x)\n}", "Quux"),
+    ] {
+        let diagnostics = compile_errors(source);
+
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|diagnostic| (diagnostic.code, diagnostic.message.clone()))
+                .collect::<Vec<_>>(),
+            [(
+                crate::diagnostic::ErrorCode::UnknownType,
+                format!("cannot find type `{}`", name)
+            )]
+        );
+    }
+
+    let diagnostics = compile_errors("x: Doubel = 1.5");
+    assert_eq!(diagnostics[0].notes, ["did you mean `Double`?"]);
+    let diagnostics = compile_errors("s: Strng = \"a\"");
+    assert_eq!(diagnostics[0].notes, ["did you mean `String`?"]);
+}
+
+#[test]
+fn undefined_names_are_reported_with_suggestions() {
+    let diagnostics = compile_errors(
+        "counter = 0\nprintln(countr)\nprnitln(counter)\nfunction add(a: SignedInt, b: SignedInt): SignedInt {\n    return a + b\n}\nprintln(add(1))\nprintln()\nprintln(nothing_like_it)",
+    );
+
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.message.as_str(), diagnostic.notes.clone()))
+            .collect::<Vec<_>>(),
+        [
+            (
+                "cannot find variable `countr`",
+                vec!["did you mean `counter`?".to_owned()]
+            ),
+            (
+                "cannot find function `prnitln`",
+                vec!["did you mean `println`?".to_owned()]
+            ),
+            ("`add` takes 2 arguments, but 1 was given", vec![]),
+            ("`println` takes 1 argument, but 0 were given", vec![]),
+            ("cannot find variable `nothing_like_it`", vec![]),
+        ]
+    );
+}
+
+#[test]
+fn names_must_be_defined_before_they_are_used() {
+    let diagnostics = compile_errors("println(x)\nx = 1\nfunction f() {\n    println(y)\n}\ny = 2");
+
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == crate::diagnostic::ErrorCode::UndefinedVariable));
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let lines = diagnostic
+                    .labels
+                    .iter()
+                    .map(|label| label.span.line)
+                    .collect::<Vec<_>>();
+                (diagnostic.message.as_str(), lines)
+            })
+            .collect::<Vec<_>>(),
+        [
+            ("`x` is used before it is defined", vec![1, 2]),
+            ("`y` is used before it is defined", vec![4, 6]),
+        ]
+    );
+}
+
+#[test]
+fn functions_can_be_called_before_they_are_defined() {
+    assert_outputs!(
+        "println(double(4))\nfunction double(n: SignedInt): SignedInt {\n    return n * 2\n}" => "8\n",
+        "function is_even(n: SignedInt): bool {\n    if n is 0 {\n        return true\n    }\n    return is_odd(n - 1)\n}\nfunction is_odd(n: SignedInt): bool {\n    if n is 0 {\n        return false\n    }\n    return is_even(n - 1)\n}\nprintln(is_even(10))\nprintln(is_odd(10))" => "true\nfalse\n"
+    );
+}
+
+#[test]
+fn functions_cant_be_defined_twice() {
+    let diagnostics = compile_errors(
+        "function f() {\n    println(1)\n}\nif true {\n    function f() {\n        println(2)\n    }\n}\nf()",
+    );
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].code,
+        crate::diagnostic::ErrorCode::Redefinition
+    );
+    assert_eq!(
+        diagnostics[0]
+            .labels
+            .iter()
+            .map(|label| (label.span.line, label.primary))
+            .collect::<Vec<_>>(),
+        [(5, true), (1, false)]
+    );
+}
+
+#[test]
+fn return_types_are_inferred() {
+    assert_outputs!(
+        "function half(n: SignedInt) {\n    return n / 2\n}\nprintln(half(9))" => "4\n",
+        "function factorial(n: UnsignedInt) {\n    if n is 0 {\n        return 1\n    }\n    return n * factorial(n - 1)\n}\nprintln(factorial(10))" => "3628800\n",
+        "function describe(b: bool) {\n    if b {\n        return \"yes\"\n    } else {\n        return \"no\"\n    }\n}\nprintln(describe(true))\nprintln(describe(false))" => "yes\nno\n",
+        "greet()\nfunction greet() {\n    println(\"hi\")\n}" => "hi\n",
+        "println(later(1))\nfunction later(n: SignedInt) {\n    return n * 2\n}" => "2\n"
+    );
+}
+
+#[test]
+fn return_types_are_checked_on_every_path() {
+    let diagnostics = compile_errors(
+        "function f(n: SignedInt): SignedInt {\n    if n > 0 {\n        return n\n    }\n}\nfunction g(b: bool) {\n    if b {\n        return 1\n    }\n    return \"one\"\n}\nfunction h(): bool {\n    return 1.5\n}\nfunction forever(n: SignedInt) {\n    return forever(n)\n}",
+    );
+
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.message.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (
+                crate::diagnostic::ErrorCode::MissingReturn,
+                "`f` doesn't return a value on every path"
+            ),
+            (
+                crate::diagnostic::ErrorCode::TypeMismatch,
+                "expected `UnsignedInt`, found `string`"
+            ),
+            (
+                crate::diagnostic::ErrorCode::TypeMismatch,
+                "expected `bool`, found `Double`"
+            ),
+            (
+                crate::diagnostic::ErrorCode::UnknownReturnType,
+                "cannot infer the return type of `forever` here"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn void_functions() {
+    assert_outputs!(
+        "function shout(s: string) {\n    println(s + \"!\")\n    return None\n}\nshout(\"hi\")\nshout(\"bye\")" => "hi!\nbye!\n",
+        "function log(n: SignedInt): void {\n    if n > 0 {\n        println(n)\n        return None\n    }\n    println(\"none\")\n}\nlog(1)\nlog(0)" => "1\nnone\n",
+        "function twice() {\n    greet()\n    greet()\n}\nfunction greet() {\n    println(\"hey\")\n}\ntwice()" => "hey\nhey\n"
+    );
+}
+
+#[test]
+fn void_values_cant_be_stored() {
+    let diagnostics = compile_errors(
+        "function f() {}\nx = f()\ny: void = 1\nfunction g(a: void) {}\nprintln(f() + 1)\nz = None",
+    );
+
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.message.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (
+                crate::diagnostic::ErrorCode::VoidValue,
+                "`x` can't be `void`"
+            ),
+            (
+                crate::diagnostic::ErrorCode::VoidValue,
+                "`y` can't have the type `void`"
+            ),
+            (
+                crate::diagnostic::ErrorCode::VoidValue,
+                "parameter `a` can't have the type `void`"
+            ),
+            (
+                crate::diagnostic::ErrorCode::TypeMismatch,
+                "cannot use `+` on `void`"
+            ),
+            (
+                crate::diagnostic::ErrorCode::VoidValue,
+                "`z` can't be `void`"
+            ),
+        ]
+    );
+}
@@ -0,0 +1,589 @@
+use crate::{
+    diagnostic::ErrorCode,
+    parser::{
+        ast::{AssignmentType, Statement, Type},
+        expr::{Expr, Op},
+        imports::ImportResolver,
+        parse_ast, parse_ast_with, JamalangFile,
+    },
+};
+
+#[test]
+fn syntax_error_has_span() {
+    let diagnostics = parse_ast("test_file", "x = 1\ny = )").expect_err("syntax should be invalid");
+    let span = diagnostics[0]
+        .primary_span()
+        .expect("syntax errors should be labelled");
+
+    assert_eq!(diagnostics[0].code, ErrorCode::Syntax);
+    assert_eq!((span.line, span.column), (2, 5));
+}
+
+#[test]
+fn array_type_hints_are_parsed() {
+    let file = parse_ast(
+        "test_file",
+        "function f(xs: number[3]): bool[2][4] {}\ngrid: char[2][3] = x\nfor row: number[3] in grid {}",
+    )
+    .expect("syntax should be valid");
+    let array = |len, kind| Type::Array {
+        len,
+        kind: Box::new(kind),
+    };
+
+    let Statement::FunctionDefinition {
+        parameters,
+        return_type_hint,
+        ..
+    } = &file.statements[0]
+    else {
+        panic!("expected a function definition");
+    };
+    assert_eq!(parameters[0].1, array(3, Type::Float));
+    assert_eq!(*return_type_hint, Some(array(2, array(4, Type::Bool))));
+    assert_eq!(
+        return_type_hint.as_ref().map(Type::to_string).as_deref(),
+        Some("bool[2][4]")
+    );
+
+    let Statement::Assignment { type_hint, .. } = &file.statements[1] else {
+        panic!("expected an assignment");
+    };
+    assert_eq!(*type_hint, Some(array(2, array(3, Type::Char))));
+
+    let Statement::ForLoop {
+        identifier_type_hint,
+        ..
+    } = &file.statements[2]
+    else {
+        panic!("expected a for loop");
+    };
+    assert_eq!(*identifier_type_hint, Some(array(3, Type::Float)));
+}
+
+#[test]
+fn oversized_array_length_is_reported() {
+    let diagnostics = parse_ast("test_file", "function f(xs: number[99999999999]) {}")
+        .expect_err("array length should be rejected");
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, ErrorCode::Syntax);
+}
+
+#[test]
+fn render_quotes_source() {
+    let source = "x = 1\ny = )";
+    let diagnostics = parse_ast("test_file", source).expect_err("syntax should be invalid");
+
+    assert_eq!(
+        diagnostics[0].render(source),
+        "error[E0001]: expected Expr\n --> test_file:2:5\n  |\n2 | y = )\n  |     ^ expected Expr\n"
+    );
+}
+
+#[test]
+fn nodes_carry_spans() {
+    let source = "x = 1\nif x is 1 {\n    y = x + 2\n}";
+    let file = parse_ast("test_file", source).expect("syntax should be valid");
+    let Statement::IfStatement { body, span, .. } = &file.statements[1] else {
+        panic!("expected an if statement");
+    };
+    let Statement::Assignment { value, .. } = &body[0] else {
+        panic!("expected an assignment");
+    };
+
+    assert_eq!((span.line, span.column), (2, 1));
+    assert_eq!(&source[value.span().start..value.span().end], "x + 2");
+    assert_eq!((value.span().line, value.span().column), (3, 9));
+    assert_eq!(&*value.span().file, "test_file");
+}
+
+/// Writes `files` into a fresh directory, returning its path.
+pub(super) fn write_files(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("jamalang_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    for (path, contents) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().expect("path should have a parent"))
+            .expect("directory should be creatable");
+        std::fs::write(path, contents).expect("file should be writable");
+    }
+
+    dir
+}
+
+fn parse_path(
+    path: &std::path::Path,
+    resolver: &mut ImportResolver,
+) -> Result<JamalangFile, Vec<crate::diagnostic::Diagnostic>> {
+    let source = std::fs::read_to_string(path).expect("file should be readable");
+    parse_ast_with(&path.to_string_lossy(), &source, resolver)
+}
+
+#[test]
+fn imports_resolve_relative_to_importer_and_deduplicate() {
+    let dir = write_files(
+        "imports_relative",
+        &[
+            ("main.jam", "import lib/a.jam\nimport lib/b.jam"),
+            ("lib/a.jam", "import b.jam"),
+            ("lib/b.jam", "x = 1"),
+        ],
+    );
+    let file = parse_path(&dir.join("main.jam"), &mut ImportResolver::default())
+        .expect("imports should resolve");
+
+    let Statement::ForeignModule { file: Some(a), .. } = &file.statements[0] else {
+        panic!("first import should be loaded");
+    };
+    assert!(matches!(
+        &a.statements[0],
+        Statement::ForeignModule { file: Some(_), .. }
+    ));
+    assert!(matches!(
+        &file.statements[1],
+        Statement::ForeignModule { file: None, .. }
+    ));
+}
+
+#[test]
+fn imports_use_search_paths() {
+    let dir = write_files(
+        "imports_search",
+        &[("main.jam", "import util.jam"), ("libs/util.jam", "x = 1")],
+    );
+
+    let errors = parse_path(&dir.join("main.jam"), &mut ImportResolver::default())
+        .expect_err("import should not be found without a search path");
+    assert_eq!(errors[0].code, ErrorCode::ImportNotFound);
+
+    parse_path(
+        &dir.join("main.jam"),
+        &mut ImportResolver::new(vec![dir.join("libs")]),
+    )
+    .expect("import should be found in the search path");
+}
+
+#[test]
+fn import_cycles_are_reported() {
+    let dir = write_files(
+        "imports_cycle",
+        &[
+            ("a.jam", "import b.jam"),
+            ("b.jam", "import c.jam"),
+            ("c.jam", "import a.jam"),
+        ],
+    );
+    let errors = parse_path(&dir.join("a.jam"), &mut ImportResolver::default())
+        .expect_err("cycle should be rejected");
+
+    assert_eq!(errors[0].code, ErrorCode::ImportCycle);
+    assert!(
+        errors[0].notes[0].contains("a.jam` -> `") && errors[0].notes[0].contains("c.jam` -> `")
+    );
+}
+
+#[test]
+fn files_with_the_same_name_cant_both_be_imported() {
+    let dir = write_files(
+        "imports_same_name",
+        &[
+            ("main.jam", "import a/util.jam\nimport b/util.jam"),
+            ("a/util.jam", "x = 1"),
+            ("b/util.jam", "y = 2"),
+        ],
+    );
+    let errors = parse_path(&dir.join("main.jam"), &mut ImportResolver::default())
+        .expect_err("second file should be rejected");
+
+    assert_eq!(errors[0].code, ErrorCode::NamespaceCollision);
+    assert!(errors[0].notes[0].contains("util.jam` is already imported as `util`"));
+}
+
+#[test]
+fn imported_file_names_must_be_identifiers() {
+    let dir = write_files(
+        "imports_invalid_name",
+        &[
+            (
+                "main.jam",
+                "import my-lib.jam\nimport 2d.jam\nimport my_lib2.jam",
+            ),
+            ("my-lib.jam", "x = 1"),
+            ("2d.jam", "y = 2"),
+            ("my_lib2.jam", "z = 3"),
+        ],
+    );
+    let errors = parse_path(&dir.join("main.jam"), &mut ImportResolver::default())
+        .expect_err("file names should be rejected");
+
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| (error.code, error.message.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (
+                ErrorCode::InvalidNamespace,
+                "`my-lib` can't be used as the name of an imported file"
+            ),
+            (
+                ErrorCode::InvalidNamespace,
+                "`2d` can't be used as the name of an imported file"
+            ),
+        ]
+    );
+    assert_eq!(errors[0].primary_span().map(|span| span.line), Some(1));
+}
+
+#[test]
+fn string_literals_are_unescaped() {
+    let file = parse_ast("test_file", "x = \"a\\tb\\\"c\"\ny = 'it\\'s'\nz = 'c'")
+        .expect("syntax should be valid");
+    let values = file
+        .statements
+        .iter()
+        .map(|statement| match statement {
+            Statement::Assignment { value, .. } => value.clone(),
+            statement => panic!("expected an assignment, found {:?}", statement),
+        })
+        .collect::<Vec<_>>();
+
+    assert!(matches!(&values[0], Expr::Str(value, _) if value == "a\tb\"c"));
+    assert!(matches!(&values[1], Expr::Str(value, _) if value == "it's"));
+    assert!(matches!(values[2], Expr::Char('c', _)));
+}
+
+#[test]
+fn string_interpolation_is_parsed() {
+    let file = parse_ast("test_file", "x = \"a {1 + 2} b \\{c}\"").expect("syntax should be valid");
+    let Statement::Assignment { value, .. } = &file.statements[0] else {
+        panic!("expected an assignment");
+    };
+    let Expr::Interpolation(parts, _) = value else {
+        panic!("expected an interpolated string, found {:?}", value);
+    };
+
+    assert_eq!(parts.len(), 3);
+    assert!(matches!(&parts[0], Expr::Str(text, _) if text == "a "));
+    assert!(matches!(&parts[1], Expr::BinOp { .. }));
+    assert!(matches!(&parts[2], Expr::Str(text, _) if text == " b {c}"));
+}
+
+#[test]
+fn object_literals_and_record_hints() {
+    let file = parse_ast(
+        "test_file",
+        "p = { y: 2, x: 1, }\nfunction f(p: { y: number, x: number }): { x: number } {}",
+    )
+    .expect("syntax should be valid");
+
+    let Statement::Assignment {
+        value: Expr::Object(fields, _),
+        ..
+    } = &file.statements[0]
+    else {
+        panic!("expected an object literal");
+    };
+    let names = fields
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["y", "x"]);
+
+    let Statement::FunctionDefinition {
+        parameters,
+        return_type_hint,
+        ..
+    } = &file.statements[1]
+    else {
+        panic!("expected a function definition");
+    };
+    assert_eq!(
+        parameters[0].1,
+        Type::Record(vec![
+            ("x".to_owned(), Type::Float),
+            ("y".to_owned(), Type::Float)
+        ])
+    );
+    assert_eq!(
+        *return_type_hint,
+        Some(Type::Record(vec![("x".to_owned(), Type::Float)]))
+    );
+}
+
+#[test]
+fn duplicate_fields_are_reported() {
+    let diagnostics = parse_ast(
+        "test_file",
+        "p = { x: 1, x: 2 }\nfunction f(p: { a: bool, a: bool }) {}",
+    )
+    .expect_err("duplicate fields should be rejected");
+
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == ErrorCode::DuplicateField));
+    assert_eq!(
+        diagnostics[0].primary_span().map(|span| span.column),
+        Some(13)
+    );
+}
+
+#[test]
+fn parameters_without_types_are_reported() {
+    let diagnostics = parse_ast(
+        "test_file",
+        "function add(a, b: SignedInt) {}
+function sub(a: SignedInt, b) {}",
+    )
+    .expect_err("untyped parameters should be rejected");
+
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == ErrorCode::MissingTypeHint));
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let span = diagnostic.primary_span().expect("should have a span");
+                (diagnostic.message.as_str(), span.line, span.column)
+            })
+            .collect::<Vec<_>>(),
+        [
+            ("parameter `a` has no type", 1, 14),
+            ("parameter `b` has no type", 2, 28),
+        ]
+    );
+}
+
+#[test]
+fn nested_indexing_is_parsed() {
+    let file = parse_ast("test_file", "grid[1][0]").expect("syntax should be valid");
+    let Statement::Expr {
+        expr: Expr::ArrayIndex { array, .. },
+        ..
+    } = &file.statements[0]
+    else {
+        panic!("expected an index expression");
+    };
+
+    assert!(matches!(**array, Expr::ArrayIndex { .. }));
+}
+
+#[test]
+fn assignment_to_nested_element_is_parsed() {
+    let file = parse_ast("test_file", "grid[y][x] *= 2").expect("syntax should be valid");
+    let Statement::Assignment {
+        identifier,
+        indices,
+        r#type,
+        ..
+    } = &file.statements[0]
+    else {
+        panic!("expected an assignment");
+    };
+
+    assert_eq!(identifier, "grid");
+    assert!(matches!(
+        indices.as_slice(),
+        [Expr::Identifier(y, _), Expr::Identifier(x, _)] if y == "y" && x == "x"
+    ));
+    assert_eq!(*r#type, AssignmentType::Mul);
+}
+
+#[test]
+fn power_is_right_associative_and_binds_tighter_than_minus() {
+    let file = parse_ast("test_file", "-2 ** 3 ** 2").expect("syntax should be valid");
+    let Statement::Expr {
+        expr: Expr::UnaryMinus(operand, _),
+        ..
+    } = &file.statements[0]
+    else {
+        panic!("expected a negation");
+    };
+    let Expr::BinOp {
+        lhs,
+        op: Op::Power,
+        rhs,
+        ..
+    } = &**operand
+    else {
+        panic!("expected a power");
+    };
+
+    assert!(matches!(**lhs, Expr::Integer(2, None, _)));
+    assert!(matches!(**rhs, Expr::BinOp { op: Op::Power, .. }));
+}
+
+#[test]
+fn both_power_assignments_are_parsed() {
+    let file = parse_ast("test_file", "x **= 2\nx ^= 2").expect("syntax should be valid");
+
+    for statement in &file.statements {
+        assert!(matches!(
+            statement,
+            Statement::Assignment {
+                r#type: AssignmentType::Pow,
+                ..
+            }
+        ));
+    }
+}
+
+#[test]
+fn bitwise_assignments_are_parsed() {
+    let file =
+        parse_ast("test_file", "x &= 2\nx |= 2\nx <<= 2\nx >>= 2").expect("syntax should be valid");
+    let types = file
+        .statements
+        .iter()
+        .map(|statement| match statement {
+            Statement::Assignment { r#type, .. } => r#type,
+            statement => panic!("expected an assignment, found {:?}", statement),
+        })
+        .collect::<Vec<_>>();
+
+    assert!(matches!(
+        types[..],
+        [
+            AssignmentType::BitAnd,
+            AssignmentType::BitOr,
+            AssignmentType::ShiftLeft,
+            AssignmentType::ShiftRight,
+        ]
+    ));
+}
+
+#[test]
+fn integer_and_float_literals_are_told_apart() {
+    let file = parse_ast("test_file", "1_000\n0.1\n0").expect("syntax should be valid");
+    let exprs = file
+        .statements
+        .iter()
+        .map(|statement| match statement {
+            Statement::Expr { expr, .. } => expr,
+            statement => panic!("expected an expression, found {:?}", statement),
+        })
+        .collect::<Vec<_>>();
+
+    assert!(matches!(exprs[0], Expr::Integer(1000, None, _)));
+    // Float literals are parsed as 64-bit, so they are exact until they are used as a `Float`.
+    assert!(matches!(exprs[1], Expr::Float(value, None, _) if *value == 0.1));
+    assert!(matches!(exprs[2], Expr::Integer(0, None, _)));
+}
+
+/// Parses a file made of a single expression, returning that expression.
+fn parse_literal(source: &str) -> Expr {
+    let file = parse_ast("test_file", source)
+        .unwrap_or_else(|diagnostics| panic!("`{}` should parse: {:?}", source, diagnostics));
+
+    match file.statements.into_iter().next() {
+        Some(Statement::Expr { expr, .. }) => expr,
+        statement => panic!("expected an expression, found {:?}", statement),
+    }
+}
+
+#[test]
+fn integer_literals_in_other_bases_and_with_suffixes() {
+    assert!(matches!(parse_literal("0xFF"), Expr::Integer(255, None, _)));
+    assert!(matches!(
+        parse_literal("0xdead_beef"),
+        Expr::Integer(0xdead_beef, None, _)
+    ));
+    assert!(matches!(
+        parse_literal("0b1010"),
+        Expr::Integer(10, None, _)
+    ));
+    assert!(matches!(
+        parse_literal("0o755"),
+        Expr::Integer(493, None, _)
+    ));
+    assert!(matches!(
+        parse_literal("10u"),
+        Expr::Integer(10, Some(Type::UnsignedInt), _)
+    ));
+    assert!(matches!(
+        parse_literal("0xFFL"),
+        Expr::Integer(255, Some(Type::Long), _)
+    ));
+}
+
+#[test]
+fn none_is_only_a_whole_word() {
+    assert!(matches!(parse_literal("None"), Expr::TypeNone(_)));
+    assert!(matches!(
+        parse_literal("Nonexistent"),
+        Expr::Identifier(name, _) if name == "Nonexistent"
+    ));
+}
+
+#[test]
+fn scientific_literals_are_floats() {
+    for (source, expected) in [
+        ("1.5e-3", 1.5e-3),
+        ("2E10", 2e10),
+        ("6.022e+23", 6.022e23),
+        ("1_000.5", 1000.5),
+    ] {
+        assert!(
+            matches!(parse_literal(source), Expr::Float(value, None, _) if value == expected),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn the_smallest_long_can_be_written_as_a_literal() {
+    for source in ["-9223372036854775808", "-9223372036854775808L"] {
+        let Expr::UnaryMinus(literal, _) = parse_literal(source) else {
+            panic!("`{}` should be negated", source);
+        };
+        assert!(
+            matches!(*literal, Expr::Integer(i64::MIN, ..)),
+            "{}",
+            source
+        );
+    }
+
+    // Without a minus that applies to the literal alone, it is one more than the largest `Long`.
+    for source in [
+        "9223372036854775808",
+        "-9223372036854775809",
+        "x = 1 - 9223372036854775808",
+        "-9223372036854775808 ** 2",
+    ] {
+        let diagnostics = parse_ast("test_file", source).expect_err("the literal shouldn't fit");
+        assert_eq!(
+            diagnostics[0].code,
+            ErrorCode::LiteralOverflow,
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn literals_that_dont_fit_their_type_are_errors() {
+    for (source, message) in [
+        (
+            "99999999999999999999",
+            "integer literal doesn't fit in `Long`",
+        ),
+        (
+            "0x1_0000_0000u",
+            "integer literal doesn't fit in `UnsignedInt`",
+        ),
+        ("1e400", "float literal doesn't fit in `Double`"),
+    ] {
+        let diagnostics = parse_ast("test_file", source).expect_err("the literal shouldn't fit");
+        assert_eq!(diagnostics[0].code, ErrorCode::LiteralOverflow);
+        assert_eq!(diagnostics[0].message, message);
+    }
+
+    // A digit that isn't allowed in the base doesn't start a new literal.
+    assert!(parse_ast("test_file", "0b102").is_err());
+    assert!(parse_ast("test_file", "0xFG").is_err());
+}
@@ -0,0 +1,151 @@
+use crate::parser::{
+    ast::Statement,
+    expr::{Expr, Op},
+    parse_ast,
+};
+
+/// Every binary operator with its precedence, from the loosest binding (0) to the tightest. All of
+/// them group from the left apart from `**`.
+const OPERATORS: &[(&str, Op, u8)] = &[
+    ("or", Op::Or, 0),
+    ("and", Op::And, 1),
+    ("is", Op::Eq, 3),
+    ("is not", Op::NotEq, 3),
+    ("<", Op::Lt, 3),
+    (">", Op::Gt, 3),
+    ("<=", Op::Lte, 3),
+    (">=", Op::Gte, 3),
+    ("..", Op::Range, 4),
+    ("|", Op::BitOr, 5),
+    ("^", Op::BitXor, 6),
+    ("&", Op::BitAnd, 7),
+    ("<<", Op::ShiftLeft, 8),
+    (">>", Op::ShiftRight, 8),
+    ("+", Op::Add, 9),
+    ("-", Op::Subtract, 9),
+    ("*", Op::Multiply, 10),
+    ("/", Op::Divide, 10),
+    ("%", Op::Modulo, 10),
+    ("**", Op::Power, 12),
+];
+
+/// The precedence of each prefix operator, on the same scale as [`OPERATORS`].
+const PREFIXES: &[(&str, u8)] = &[("not ", 2), ("-", 11), ("~", 11)];
+
+fn parse(source: &str) -> Expr {
+    let file = parse_ast("test_file", source)
+        .unwrap_or_else(|diagnostics| panic!("`{}` should parse: {:?}", source, diagnostics));
+
+    match file.statements.into_iter().next() {
+        Some(Statement::Expr { expr, .. }) => expr,
+        statement => panic!(
+            "`{}` should be an expression, found {:?}",
+            source, statement
+        ),
+    }
+}
+
+/// Writes an expression out with every operation in brackets, such as `((a + b) < c)`.
+fn shape(expr: &Expr) -> String {
+    match expr {
+        Expr::Identifier(name, _) => name.clone(),
+        Expr::UnaryMinus(operand, _) => format!("(-{})", shape(operand)),
+        Expr::Not(operand, _) => format!("(not {})", shape(operand)),
+        Expr::BitNot(operand, _) => format!("(~{})", shape(operand)),
+        Expr::Cast { value, r#type, .. } => format!("({} as {})", shape(value), r#type),
+        Expr::BinOp { lhs, op, rhs, .. } => {
+            let symbol = OPERATORS
+                .iter()
+                .find(|(_, other, _)| other == op)
+                .map(|(symbol, _, _)| symbol)
+                .expect("every operator should be listed");
+            format!("({} {} {})", shape(lhs), symbol, shape(rhs))
+        }
+        expr => panic!("unexpected expression {:?}", expr),
+    }
+}
+
+#[test]
+fn every_pair_of_operators_groups_by_precedence() {
+    let mut mismatches = Vec::new();
+
+    for (first, first_op, first_level) in OPERATORS {
+        for (second, _, second_level) in OPERATORS {
+            let source = format!("a {} b {} c", first, second);
+            let groups_right = second_level > first_level
+                || (second_level == first_level && *first_op == Op::Power);
+            let expected = if groups_right {
+                format!("(a {} (b {} c))", first, second)
+            } else {
+                format!("((a {} b) {} c)", first, second)
+            };
+
+            let found = shape(&parse(&source));
+            if found != expected {
+                mismatches.push(format!(
+                    "`{}`: expected {}, found {}",
+                    source, expected, found
+                ));
+            }
+        }
+    }
+
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+#[test]
+fn prefix_operators_group_by_precedence() {
+    for (prefix, prefix_level) in PREFIXES {
+        for (symbol, _, level) in OPERATORS {
+            let expected = if level > prefix_level {
+                format!("({}(a {} b))", prefix, symbol)
+            } else {
+                format!("(({}a) {} b)", prefix, symbol)
+            };
+            assert_eq!(
+                shape(&parse(&format!("{}a {} b", prefix, symbol))),
+                expected
+            );
+
+            // `a is not b` is the `is not` operator rather than `is` applied to `not b`.
+            if (*symbol, *prefix) != ("is", "not ") {
+                assert_eq!(
+                    shape(&parse(&format!("a {} {}b", symbol, prefix))),
+                    format!("(a {} ({}b))", symbol, prefix)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn brackets_override_precedence() {
+    assert_eq!(shape(&parse("(a + b) * c")), "((a + b) * c)");
+    assert_eq!(shape(&parse("a ** (b + c)")), "(a ** (b + c))");
+    assert_eq!(shape(&parse("(a ** b) ** c")), "((a ** b) ** c)");
+    assert_eq!(shape(&parse("(-a) ** b")), "((-a) ** b)");
+    assert_eq!(shape(&parse("(not a) is b")), "((not a) is b)");
+}
+
+#[test]
+fn keywords_only_match_whole_words() {
+    assert_eq!(
+        shape(&parse("order and nothing or android")),
+        "((order and nothing) or android)"
+    );
+    assert_eq!(shape(&parse("a is nothing")), "(a is nothing)");
+    assert_eq!(shape(&parse("a + ask")), "(a + ask)");
+}
+
+#[test]
+fn casts_bind_between_multiplication_and_negation() {
+    assert_eq!(shape(&parse("a * b as Long")), "(a * (b as Long))");
+    assert_eq!(shape(&parse("-a as Long")), "((-a) as Long)");
+    assert_eq!(shape(&parse("~a as Long")), "((~a) as Long)");
+    assert_eq!(shape(&parse("a ** b as Long")), "((a ** b) as Long)");
+    assert_eq!(shape(&parse("not a as bool")), "(not (a as bool))");
+    assert_eq!(
+        shape(&parse("a as Long as Double")),
+        "((a as Long) as Double)"
+    );
+}